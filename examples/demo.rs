@@ -0,0 +1,3193 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
+use iced::keyboard::{self, Key, key::Named};
+use iced::widget::operation::{self, AbsoluteOffset};
+use iced::widget::{button, checkbox, column, pick_list, row, scrollable, text, text_input};
+use iced::{Element, Length, Subscription, Task};
+use iced_tree::clipboard::Contents;
+use iced_tree::filesystem::{self, Payload};
+use iced_tree::replace::{Rename, Replace};
+use iced_tree::search::{self, Direction};
+use iced_tree::sort::{self, Order};
+use iced_tree::tree_view::{self, DEFAULT_ROW_HEIGHT, Decoration};
+use iced_tree::{navigation, opml, outline, watch};
+use iced_tree::{
+    CheckState, Checks, Clipboard, DropPosition, Edit, Filter, History, Load, Navigation, NodeId,
+    Search, SelectMode, Selection, Step, TreeView, tree,
+};
+
+/// Demo nodes carry a filesystem entry when they were read from a folder
+type Node = tree::Node<Payload>;
+
+/// Id of the scrollable wrapping the tree, used to keep the cursor in view
+const TREE_SCROLLABLE: &str = "tree";
+/// Rows moved by PageUp/PageDown before the viewport size is known
+const DEFAULT_PAGE_ROWS: usize = 10;
+/// Label changes listed in the replace panel before the rest are summed up
+const PREVIEW_ROWS: usize = 8;
+
+fn main() -> iced::Result {
+    iced::application(TreeDemo::new, TreeDemo::update, TreeDemo::view)
+        .title(TreeDemo::title)
+        .subscription(TreeDemo::subscription)
+        .run()
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Toggle(NodeId),
+    /// The children read for a node opened while unloaded, or why they
+    /// could not be read
+    Loaded(NodeId, Result<Vec<Node>, String>),
+    /// Reads the children of a node that failed to load again
+    Retry(NodeId),
+    StartEdit(NodeId),
+    EditLabel(String),
+    FinishEdit,
+    CancelEdit,
+    EditBlurred,
+    AddChild(NodeId),
+    RemoveChild(NodeId),
+    AddRoot,
+    RemoveRoot(NodeId),
+    RequestRemove(NodeId),
+    ConfirmRemove,
+    CancelRemove,
+    Undo,
+    Redo,
+    MoveNode {
+        from: NodeId,
+        to_parent: Option<NodeId>,
+        index: usize,
+    },
+    Drop(NodeId, NodeId, DropPosition),
+    Copy,
+    Cut,
+    Paste(Placement),
+    Pasted(Placement, Option<Contents>),
+    Duplicate,
+    Open,
+    Opened(Result<(PathBuf, Vec<Node>), Error>),
+    OpenFolder,
+    FolderOpened(Result<Node, Error>),
+    /// The folder watch started and can be told which folders to watch
+    WatchStarted(watch::Handle),
+    /// A file or folder shown in the tree changed on disk
+    Changed(watch::Change),
+    Save,
+    SaveAs,
+    /// Result of writing the tree as it was at the given history revision
+    Saved(u64, Result<PathBuf, Error>),
+    Select(NodeId, SelectMode),
+    SelectAll,
+    Navigate(Navigation),
+    EditFocused,
+    RemoveFocused,
+    Scrolled(scrollable::Viewport),
+    /// The text typed into the filter box
+    FilterChanged(String),
+    /// The text typed into the find bar
+    FindChanged(String),
+    FindModeChanged(search::Mode),
+    /// Focuses the next or previous match of the find bar's search
+    Find(Direction),
+    /// Shows or hides the find-and-replace panel
+    ToggleReplace,
+    ReplaceFindChanged(String),
+    ReplaceWithChanged(String),
+    ReplaceRegex(bool),
+    ReplaceInSubtree(bool),
+    /// Renames every node listed in the replace panel's preview
+    ReplaceAll,
+    SortOrderChanged(Order),
+    /// Sorts the children of the focused node, or the roots when nothing is
+    /// focused; with `recursive` all levels below as well
+    Sort { recursive: bool },
+    KeepSorted(bool),
+    ShowChecks(bool),
+    /// Checks or unchecks a node together with its descendants
+    ToggleCheck(NodeId),
+    /// Copies the labels of the checked nodes as plain text, one per line
+    CopyChecked,
+}
+
+/// Why opening or saving a file failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The file dialog was dismissed
+    DialogClosed,
+    Io(io::ErrorKind),
+    /// The file cannot be parsed in its format; holds the parser's description
+    Malformed(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::DialogClosed => write!(f, "No file chosen"),
+            Error::Io(kind) => write!(f, "Could not access the file: {kind}"),
+            Error::Malformed(reason) => write!(f, "Malformed tree file: {reason}"),
+        }
+    }
+}
+
+/// Where pasted nodes go relative to the focused node
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    /// Right after the focused node, under the same parent
+    Sibling,
+    /// As the last children of the focused node
+    Child,
+}
+
+/// What happens to a pending label edit when it is interrupted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PendingEdit {
+    /// Write the edited label into the node
+    Commit,
+    /// Drop the edited label, keeping the original
+    Cancel,
+    /// Leave the edit running and ignore the interruption
+    Keep,
+}
+
+/// How an in-progress edit reacts to interruptions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EditPolicy {
+    /// Applied when `StartEdit` arrives for another node
+    pub on_restart: PendingEdit,
+    /// Applied when the mouse is pressed outside the text input
+    pub on_blur: PendingEdit,
+}
+
+impl Default for EditPolicy {
+    fn default() -> Self {
+        Self {
+            on_restart: PendingEdit::Commit,
+            on_blur: PendingEdit::Commit,
+        }
+    }
+}
+
+pub struct TreeDemo {
+    roots: Vec<Node>,
+    editing: Option<NodeId>,
+    edit_value: String,
+    edit_policy: EditPolicy,
+    selection: Selection,
+    focused: Option<NodeId>,
+    viewport: Option<scrollable::Viewport>,
+    /// Node with children waiting for the user to confirm its removal
+    pending_removal: Option<NodeId>,
+    history: History<Payload>,
+    clipboard: Clipboard<Payload>,
+    /// File the tree was opened from or last saved to
+    file: Option<PathBuf>,
+    /// History revision of the last save or load, see [`TreeDemo::is_dirty`]
+    saved_revision: u64,
+    /// Message about the last failed file operation
+    error: Option<String>,
+    /// Narrows the tree down to matching nodes while not empty
+    filter: Filter,
+    /// Text typed into the find bar
+    find: String,
+    find_mode: search::Mode,
+    /// Search built from `find` and `find_mode`; fails for an invalid regex
+    search: Result<Search, regex::Error>,
+    /// Whether the find-and-replace panel is shown
+    replacing: bool,
+    replace_find: String,
+    replace_with: String,
+    /// Take `replace_find` as a regular expression rather than literal text
+    replace_regex: bool,
+    /// Only replace within the focused node and its descendants
+    replace_in_subtree: bool,
+    sort_order: Order,
+    /// Put added and renamed nodes at their sorted position
+    keep_sorted: bool,
+    /// Draw a checkbox on every row
+    show_checks: bool,
+    checks: Checks,
+    /// State of every node's checkbox, worked out after updates that can
+    /// change it while the checkboxes are shown
+    check_states: HashMap<NodeId, CheckState>,
+    /// Folders read from disk whose changes are reported
+    watches: watch::Watches,
+    /// Paths of the nodes by id, rebuilt when a lookup finds it out of date
+    index: RefCell<tree::Index>,
+}
+
+impl TreeDemo {
+    fn new() -> Self {
+        Self {
+            roots: vec![
+                plain(
+                    "Root 1",
+                    true,
+                    vec![
+                        plain(
+                            "Branch 1.1",
+                            false,
+                            vec![
+                                plain("Leaf 1.1.1", false, vec![]),
+                                plain("Leaf 1.1.2", false, vec![]),
+                            ],
+                        ),
+                        plain(
+                            "Branch 1.2",
+                            false,
+                            vec![plain("Leaf 1.2.1", false, vec![])],
+                        ),
+                    ],
+                ),
+                plain(
+                    "Root 2",
+                    true,
+                    vec![
+                        plain("Branch 2.1", false, vec![]),
+                        plain("Branch 2.2", false, vec![]),
+                    ],
+                ),
+            ],
+            editing: None,
+            edit_value: String::new(),
+            edit_policy: EditPolicy::default(),
+            selection: Selection::new(),
+            focused: None,
+            viewport: None,
+            pending_removal: None,
+            history: History::new(),
+            clipboard: Clipboard::new(),
+            file: None,
+            saved_revision: 0,
+            error: None,
+            filter: Filter::default(),
+            find: String::new(),
+            find_mode: search::Mode::default(),
+            search: Search::new("", search::Mode::default()),
+            replacing: false,
+            replace_find: String::new(),
+            replace_with: String::new(),
+            replace_regex: false,
+            replace_in_subtree: false,
+            sort_order: Order::default(),
+            keep_sorted: false,
+            show_checks: false,
+            checks: Checks::new(),
+            check_states: HashMap::new(),
+            watches: watch::Watches::new(),
+            index: RefCell::default(),
+        }
+    }
+
+    fn update(&mut self, message: Message) -> Task<Message> {
+        // Watched folders and check states only change with the tree or the
+        // checks: after edits, which move the history revision, and after
+        // the messages below
+        let revision = self.history.revision();
+        let refresh = matches!(
+            message,
+            Message::Loaded(..)
+                | Message::Opened(_)
+                | Message::FolderOpened(_)
+                | Message::Changed(_)
+                | Message::ShowChecks(_)
+                | Message::ToggleCheck(_)
+        );
+        let task = self.handle(message);
+        if refresh || self.history.revision() != revision {
+            self.watches.update(&self.roots);
+            self.check_states = if self.show_checks {
+                self.checks.states(&self.roots)
+            } else {
+                HashMap::new()
+            };
+        }
+        task
+    }
+
+    fn handle(&mut self, message: Message) -> Task<Message> {
+        match message {
+            Message::Toggle(id) => {
+                // Toggle what the row shows: a node the filter only expands
+                // to reveal a match is already shown open and stays as it is
+                let shown = self
+                    .rows()
+                    .iter()
+                    .find(|row| row.node.id() == id)
+                    .map(|row| row.open);
+                if let Some(node) = self.node(id) {
+                    let open = !shown.unwrap_or(node.open);
+                    if open == node.open {
+                        return Task::none();
+                    }
+                    if open {
+                        return self.open(id);
+                    }
+                    self.history.apply(&mut self.roots, Edit::SetOpen { id, open });
+                }
+            }
+            Message::Loaded(id, result) => {
+                // Loading is not an edit, so it stays out of the history
+                let checked = self
+                    .node(id)
+                    .is_some_and(|node| self.checks.state(node) == CheckState::Checked);
+                let Some(node) = self.node_mut(id) else {
+                    return Task::none();
+                };
+                if node.load != Load::Loading {
+                    return Task::none();
+                }
+                match result {
+                    Ok(children) => {
+                        // Children added while loading go after the loaded ones
+                        let added = std::mem::replace(&mut node.children, children);
+                        node.children.extend(added);
+                        node.load = Load::Loaded;
+                    }
+                    Err(reason) => node.load = Load::Failed(reason),
+                }
+                // Loaded children of a checked folder start out checked
+                if checked {
+                    self.checks.set(&self.roots, id, true);
+                }
+            }
+            Message::Retry(id) => {
+                return self.load_children(id);
+            }
+            Message::StartEdit(id) => {
+                match self.editing {
+                    Some(editing) if editing == id => return Task::none(),
+                    Some(_) => match self.edit_policy.on_restart {
+                        PendingEdit::Commit => self.commit_edit(),
+                        PendingEdit::Cancel => self.cancel_edit(),
+                        PendingEdit::Keep => return Task::none(),
+                    },
+                    None => {}
+                }
+
+                // Find the node and start editing
+                if let Some(node) = self.node(id) {
+                    self.edit_value = node.label.clone();
+                    self.editing = Some(id);
+                }
+            }
+            Message::EditLabel(value) => {
+                self.edit_value = value;
+            }
+            Message::FinishEdit => {
+                self.commit_edit();
+            }
+            Message::CancelEdit => {
+                self.cancel_edit();
+            }
+            Message::EditBlurred => match self.edit_policy.on_blur {
+                PendingEdit::Commit => self.commit_edit(),
+                PendingEdit::Cancel => self.cancel_edit(),
+                PendingEdit::Keep => {}
+            },
+            Message::AddChild(id) => {
+                if self.node(id).is_some() {
+                    let new_child = plain("New Node", false, vec![]);
+                    let index = self.insert_index(Some(id), &new_child);
+                    self.history.begin();
+                    self.history.apply(
+                        &mut self.roots,
+                        Edit::Insert {
+                            parent: Some(id),
+                            index,
+                            node: new_child,
+                        },
+                    );
+                    // Open the parent to show the new child
+                    let load = self.open(id);
+                    self.history.commit();
+                    return load;
+                }
+            }
+            Message::RemoveChild(id) => {
+                // Path should have at least 2 elements: parent and child index
+                let Some(path) = self.path_of(id) else {
+                    return Task::none();
+                };
+                if path.len() < 2 {
+                    return Task::none();
+                }
+                
+                let row = self.visible_index(id);
+                self.history.apply(&mut self.roots, Edit::Remove { id });
+                self.after_removal(row);
+            }
+            Message::AddRoot => {
+                let root = plain("New Root", false, vec![]);
+                let id = root.id();
+                let index = self.insert_index(None, &root);
+                self.history.apply(
+                    &mut self.roots,
+                    Edit::Insert {
+                        parent: None,
+                        index,
+                        node: root,
+                    },
+                );
+                self.focused = Some(id);
+                self.selection.select(id);
+                return self.scroll_to_focused();
+            }
+            Message::RemoveRoot(id) => {
+                if !self.roots.iter().any(|root| root.id() == id) {
+                    return Task::none();
+                }
+                let row = self.visible_index(id);
+
+                self.history.apply(&mut self.roots, Edit::Remove { id });
+                self.after_removal(row);
+            }
+            Message::RequestRemove(id) => {
+                let Some(node) = self.node(id) else {
+                    return Task::none();
+                };
+                if node.children.is_empty() {
+                    return self.update(self.remove_message(id));
+                }
+                self.pending_removal = Some(id);
+            }
+            Message::ConfirmRemove => {
+                if let Some(id) = self.pending_removal.take() {
+                    return self.update(self.remove_message(id));
+                }
+            }
+            Message::CancelRemove => {
+                self.pending_removal = None;
+            }
+            Message::MoveNode {
+                from,
+                to_parent,
+                index,
+            } => {
+                let Some(path) = self.path_of(from) else {
+                    return Task::none();
+                };
+                let parent = tree::get(&self.roots, &path[..path.len() - 1]).map(Node::id);
+                let siblings = match to_parent {
+                    Some(id) => self.node(id).map_or(0, |node| node.children.len()),
+                    None => self.roots.len(),
+                };
+                // Moving a node onto its own position would only add noise to the history
+                let current = path[path.len() - 1];
+                if parent == to_parent
+                    && siblings
+                        .checked_sub(1)
+                        .is_some_and(|last| index.min(last) == current)
+                {
+                    return Task::none();
+                }
+
+                self.history.begin();
+                let moved = self.history.apply(
+                    &mut self.roots,
+                    Edit::Move {
+                        id: from,
+                        parent: to_parent,
+                        index,
+                    },
+                );
+                // Show the moved node inside a collapsed parent
+                let load = match to_parent.filter(|_| moved) {
+                    Some(id) => self.open(id),
+                    None => Task::none(),
+                };
+                self.history.commit();
+                return load;
+            }
+            Message::Drop(id, target, position) => {
+                if let Some((to_parent, index)) =
+                    tree::drop_destination(&self.roots, id, target, position)
+                {
+                    return self.update(Message::MoveNode {
+                        from: id,
+                        to_parent,
+                        index,
+                    });
+                }
+            }
+            Message::Copy => {
+                if let Some(contents) = self.copy_targets() {
+                    return Task::future(write_clipboard(contents)).discard();
+                }
+            }
+            Message::Cut => {
+                let Some(contents) = self.copy_targets() else {
+                    return Task::none();
+                };
+
+                let row = self.focused.and_then(|id| self.visible_index(id));
+                self.history.begin();
+                for id in self.targets() {
+                    self.history.apply(&mut self.roots, Edit::Remove { id });
+                }
+                self.history.commit();
+                self.after_removal(row);
+                return Task::future(write_clipboard(contents)).discard();
+            }
+            Message::Paste(placement) => {
+                return Task::perform(read_clipboard(), move |contents| {
+                    Message::Pasted(placement, contents)
+                });
+            }
+            Message::Pasted(placement, contents) => {
+                if self.focused.is_some_and(|id| self.node(id).is_none()) {
+                    self.error = Some("Could not paste: the focused node no longer exists".into());
+                    return Task::none();
+                }
+                let nodes = match self.clipboard.paste(&contents.unwrap_or_default()) {
+                    Ok(nodes) => nodes,
+                    Err(error) => {
+                        self.error = Some(format!("Could not paste: {error}"));
+                        return Task::none();
+                    }
+                };
+                let Some(first) = nodes.first().map(Node::id) else {
+                    return Task::none();
+                };
+                let (parent, index) = match (self.focused, placement) {
+                    (Some(id), Placement::Child) => (Some(id), usize::MAX),
+                    (Some(id), Placement::Sibling) => self.position_after(id),
+                    (None, _) => (None, usize::MAX),
+                };
+
+                let ids: Vec<_> = nodes.iter().map(Node::id).collect();
+                self.history.begin();
+                for (offset, node) in nodes.into_iter().enumerate() {
+                    self.history.apply(
+                        &mut self.roots,
+                        Edit::Insert {
+                            parent,
+                            index: index.saturating_add(offset),
+                            node,
+                        },
+                    );
+                }
+                let load = match parent.filter(|_| placement == Placement::Child) {
+                    Some(id) => self.open(id),
+                    None => Task::none(),
+                };
+                self.history.commit();
+
+                self.select_only(&ids);
+                self.focused = Some(first);
+                return Task::batch([load, self.scroll_to_focused()]);
+            }
+            Message::Duplicate => {
+                let targets = self.targets();
+                let mut copies = Vec::new();
+
+                self.history.begin();
+                for id in targets {
+                    let Some(copy) = self.node(id).map(Node::duplicate) else {
+                        continue;
+                    };
+                    let (parent, index) = self.position_after(id);
+                    copies.push(copy.id());
+                    self.history.apply(
+                        &mut self.roots,
+                        Edit::Insert {
+                            parent,
+                            index,
+                            node: copy,
+                        },
+                    );
+                }
+                self.history.commit();
+
+                if let Some(&first) = copies.first() {
+                    self.select_only(&copies);
+                    self.focused = Some(first);
+                    return self.scroll_to_focused();
+                }
+            }
+            Message::Open => {
+                return Task::perform(open_file(), Message::Opened);
+            }
+            Message::Opened(Ok((path, roots))) => {
+                self.replace_roots(roots);
+                self.file = Some(path);
+            }
+            Message::OpenFolder => {
+                return Task::perform(open_folder(), Message::FolderOpened);
+            }
+            Message::FolderOpened(Ok(root)) => {
+                self.replace_roots(vec![root]);
+                self.file = None;
+            }
+            Message::Save => {
+                let path = self.file.clone();
+                return self.save_task(path);
+            }
+            Message::SaveAs => {
+                return self.save_task(None);
+            }
+            Message::Saved(revision, Ok(path)) => {
+                self.file = Some(path);
+                self.saved_revision = revision;
+                self.error = None;
+            }
+            Message::WatchStarted(handle) => {
+                self.watches.connect(handle);
+            }
+            Message::Changed(change) => {
+                let row = self.focused.and_then(|id| self.visible_index(id));
+                if watch::apply(&mut self.roots, &change) {
+                    self.after_removal(row);
+                }
+            }
+            Message::Opened(Err(error))
+            | Message::FolderOpened(Err(error))
+            | Message::Saved(_, Err(error)) => {
+                if error != Error::DialogClosed {
+                    self.error = Some(error.to_string());
+                }
+            }
+            Message::Undo => {
+                let row = self.focused.and_then(|id| self.visible_index(id));
+                if self.history.undo(&mut self.roots) {
+                    self.after_removal(row);
+                    return self.resume_loads();
+                }
+            }
+            Message::Redo => {
+                let row = self.focused.and_then(|id| self.visible_index(id));
+                if self.history.redo(&mut self.roots) {
+                    self.after_removal(row);
+                    return self.resume_loads();
+                }
+            }
+            Message::Select(id, mode) => {
+                self.selection
+                    .apply_rows(&self.filter.rows(&self.roots), id, mode);
+                self.focused = Some(id);
+            }
+            Message::SelectAll => {
+                self.selection
+                    .select_all_rows(&self.filter.rows(&self.roots));
+            }
+            Message::Navigate(navigation) => {
+                let rows = self.rows();
+                let page = self.page_rows(&rows, navigation);
+                match navigation::navigate_rows(&rows, self.focused, navigation, page) {
+                    Some(Step::Focus(id)) => {
+                        self.focused = Some(id);
+                        self.selection.select(id);
+                        return self.scroll_to_focused();
+                    }
+                    Some(Step::Open(id)) | Some(Step::Close(id)) => {
+                        return self.update(Message::Toggle(id));
+                    }
+                    None => {}
+                }
+            }
+            Message::EditFocused => {
+                if let Some(id) = self.focused {
+                    return self.update(Message::StartEdit(id));
+                }
+            }
+            Message::RemoveFocused => {
+                if let Some(id) = self.focused {
+                    return self.update(Message::RequestRemove(id));
+                }
+            }
+            Message::Scrolled(viewport) => {
+                self.viewport = Some(viewport);
+            }
+            Message::FilterChanged(query) => {
+                self.filter = Filter::new(query);
+                // Bring the cursor back into view once the whole tree is shown
+                if self.filter.is_empty() {
+                    return self.scroll_to_focused();
+                }
+            }
+            Message::FindChanged(query) => {
+                self.find = query;
+                self.search = Search::new(&self.find, self.find_mode);
+            }
+            Message::FindModeChanged(mode) => {
+                self.find_mode = mode;
+                self.search = Search::new(&self.find, self.find_mode);
+            }
+            Message::Find(direction) => {
+                let next = self
+                    .search
+                    .as_ref()
+                    .ok()
+                    .and_then(|search| search.next(&self.roots, self.focused, direction));
+                if let Some(id) = next {
+                    let load = self.reveal(id);
+                    self.focused = Some(id);
+                    self.selection.select(id);
+                    return Task::batch([load, self.scroll_to_focused()]);
+                }
+            }
+            Message::ToggleReplace => {
+                self.replacing = !self.replacing;
+            }
+            Message::ReplaceFindChanged(find) => {
+                self.replace_find = find;
+            }
+            Message::ReplaceWithChanged(replacement) => {
+                self.replace_with = replacement;
+            }
+            Message::ReplaceRegex(regex) => {
+                self.replace_regex = regex;
+            }
+            Message::ReplaceInSubtree(in_subtree) => {
+                self.replace_in_subtree = in_subtree;
+            }
+            Message::ReplaceAll => {
+                let Ok(renames) = self.replace_preview() else {
+                    return Task::none();
+                };
+                self.history.begin();
+                for rename in renames {
+                    let id = rename.id;
+                    self.history.apply(&mut self.roots, rename.edit());
+                    self.keep_in_order(id);
+                }
+                self.history.commit();
+            }
+            Message::SortOrderChanged(order) => {
+                self.sort_order = order;
+                if self.keep_sorted {
+                    self.sort(None, true);
+                }
+            }
+            Message::Sort { recursive } => {
+                self.sort(self.focused, recursive);
+            }
+            Message::KeepSorted(keep_sorted) => {
+                self.keep_sorted = keep_sorted;
+                if keep_sorted {
+                    self.sort(None, true);
+                }
+            }
+            Message::ShowChecks(show_checks) => {
+                self.show_checks = show_checks;
+            }
+            Message::ToggleCheck(id) => {
+                self.checks.toggle(&self.roots, id);
+            }
+            Message::CopyChecked => {
+                let labels: Vec<&str> = self
+                    .checked()
+                    .into_iter()
+                    .filter_map(|id| self.node(id))
+                    .map(|node| node.label.as_str())
+                    .collect();
+                let contents = Contents::text(labels.join("\n"));
+                return Task::future(write_clipboard(contents)).discard();
+            }
+        }
+        Task::none()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        let changes = watch::changes().map(|event| match event {
+            watch::Event::Started(handle) => Message::WatchStarted(handle),
+            watch::Event::Changed(change) => Message::Changed(change),
+        });
+
+        let keys = keyboard::listen().filter_map(|event| match event {
+            keyboard::Event::KeyPressed { key, modifiers, .. } => match key.as_ref() {
+                Key::Character("a") if modifiers.command() => Some(Message::SelectAll),
+                Key::Character(c) if modifiers.command() && c.eq_ignore_ascii_case("z") => {
+                    if modifiers.shift() {
+                        Some(Message::Redo)
+                    } else {
+                        Some(Message::Undo)
+                    }
+                }
+                Key::Character("y") if modifiers.command() => Some(Message::Redo),
+                Key::Character("c") if modifiers.command() => Some(Message::Copy),
+                Key::Character("x") if modifiers.command() => Some(Message::Cut),
+                Key::Character("d") if modifiers.command() => Some(Message::Duplicate),
+                Key::Character("h") if modifiers.command() => Some(Message::ToggleReplace),
+                Key::Character(c) if modifiers.command() && c.eq_ignore_ascii_case("o") => {
+                    if modifiers.shift() {
+                        Some(Message::OpenFolder)
+                    } else {
+                        Some(Message::Open)
+                    }
+                }
+                Key::Character(c) if modifiers.command() && c.eq_ignore_ascii_case("s") => {
+                    if modifiers.shift() {
+                        Some(Message::SaveAs)
+                    } else {
+                        Some(Message::Save)
+                    }
+                }
+                Key::Character(c) if modifiers.command() && c.eq_ignore_ascii_case("v") => {
+                    if modifiers.shift() {
+                        Some(Message::Paste(Placement::Child))
+                    } else {
+                        Some(Message::Paste(Placement::Sibling))
+                    }
+                }
+                Key::Named(Named::F2) => Some(Message::EditFocused),
+                Key::Named(Named::F3) if modifiers.shift() => {
+                    Some(Message::Find(Direction::Backward))
+                }
+                Key::Named(Named::F3) => Some(Message::Find(Direction::Forward)),
+                Key::Named(Named::Enter) => Some(Message::FinishEdit),
+                Key::Named(Named::Escape) => Some(Message::CancelEdit),
+                Key::Named(Named::Delete) => Some(Message::RemoveFocused),
+                _ => Navigation::from_key(&key).map(Message::Navigate),
+            },
+            _ => None,
+        });
+
+        Subscription::batch([keys, changes])
+    }
+
+    fn title(&self) -> String {
+        let name = self
+            .file
+            .as_deref()
+            .and_then(Path::file_name)
+            .map_or("Untitled".into(), |name| name.to_string_lossy());
+        let dirty = if self.is_dirty() { "*" } else { "" };
+        format!("{name}{dirty} - Tree Demo")
+    }
+
+    /// Whether the tree changed since it was last opened or saved
+    fn is_dirty(&self) -> bool {
+        self.history.revision() != self.saved_revision
+    }
+
+    /// Writes the tree to `path`, asking for one first when it is None
+    fn save_task(&self, path: Option<PathBuf>) -> Task<Message> {
+        let revision = self.history.revision();
+        let roots = self.roots.iter().map(Node::duplicate).collect();
+        Task::perform(save_file(path, roots), move |result| {
+            Message::Saved(revision, result)
+        })
+    }
+
+    /// Opens a node as a recorded edit, and starts reading its children if
+    /// they are not loaded yet, like a click on its arrow
+    fn open(&mut self, id: NodeId) -> Task<Message> {
+        self.history.apply(&mut self.roots, Edit::SetOpen { id, open: true });
+        self.load_children(id)
+    }
+
+    /// Starts reading the children of an unloaded node, or of one that
+    /// failed to load. Only folders read from disk have a loader.
+    fn load_children(&mut self, id: NodeId) -> Task<Message> {
+        let Some(node) = self.node_mut(id) else {
+            return Task::none();
+        };
+        if !node.load.can_start() {
+            return Task::none();
+        }
+        let Some(entry) = node.data.as_ref().filter(|entry| entry.is_folder()) else {
+            return Task::none();
+        };
+
+        let path = entry.path.clone();
+        node.load = Load::Loading;
+        Task::perform(filesystem::read(path), move |result| {
+            Message::Loaded(id, result)
+        })
+    }
+
+    /// Starts reading the children of every open node left unloaded, e.g.
+    /// one whose read was dropped while an undone removal had it detached
+    fn resume_loads(&mut self) -> Task<Message> {
+        let ids: Vec<NodeId> = tree::visible_rows(&self.roots)
+            .iter()
+            .filter(|row| row.node.open && row.node.load == Load::Unloaded)
+            .map(|row| row.node.id())
+            .collect();
+        Task::batch(ids.into_iter().map(|id| self.load_children(id)))
+    }
+
+    /// Shows a newly loaded forest, starting with a clean history
+    fn replace_roots(&mut self, roots: Vec<Node>) {
+        self.roots = roots;
+        self.error = None;
+        self.history.clear();
+        self.saved_revision = self.history.revision();
+        self.cancel_edit();
+        self.selection.clear();
+        self.checks.clear();
+        self.focused = None;
+        self.pending_removal = None;
+    }
+
+    /// Ids of the checked nodes, parents before their children
+    fn checked(&self) -> Vec<NodeId> {
+        self.checks.checked(&self.roots)
+    }
+
+    /// Number of checked nodes, counted from `check_states`
+    fn checked_count(&self) -> usize {
+        self.check_states
+            .values()
+            .filter(|&&state| state == CheckState::Checked)
+            .count()
+    }
+
+    /// Nodes that clipboard commands act on: the selected subtrees, or the
+    /// focused node when nothing is selected
+    fn targets(&self) -> Vec<NodeId> {
+        if self.selection.is_empty() {
+            self.focused.into_iter().collect()
+        } else {
+            self.selection.topmost(&self.roots)
+        }
+    }
+
+    /// Copies the target subtrees into the clipboard and returns what to
+    /// write to the system clipboard, or None when nothing is targeted or
+    /// the copy failed, which is reported in `error`
+    fn copy_targets(&mut self) -> Option<Contents> {
+        let paths: Vec<_> = self
+            .targets()
+            .into_iter()
+            .filter_map(|id| self.path_of(id))
+            .collect();
+        let nodes: Vec<_> = paths
+            .iter()
+            .filter_map(|path| tree::get(&self.roots, path))
+            .collect();
+        if nodes.is_empty() {
+            return None;
+        }
+        match self.clipboard.copy(nodes) {
+            Ok(contents) => Some(contents),
+            Err(error) => {
+                self.error = Some(format!("Could not copy: {error}"));
+                None
+            }
+        }
+    }
+
+    /// Parent and index that place a new node right after `id`
+    fn position_after(&self, id: NodeId) -> (Option<NodeId>, usize) {
+        let Some(path) = self.path_of(id) else {
+            return (None, usize::MAX);
+        };
+        let parent = tree::get(&self.roots, &path[..path.len() - 1]).map(Node::id);
+        (parent, path[path.len() - 1] + 1)
+    }
+
+    /// Replaces the selection with the given nodes
+    fn select_only(&mut self, ids: &[NodeId]) {
+        self.selection.clear();
+        for &id in ids {
+            self.selection.toggle(id);
+        }
+    }
+
+    /// Picks the removal message matching the node's position
+    fn remove_message(&self, id: NodeId) -> Message {
+        if self.roots.iter().any(|root| root.id() == id) {
+            Message::RemoveRoot(id)
+        } else {
+            Message::RemoveChild(id)
+        }
+    }
+
+    /// Drops state that referred to removed nodes, e.g. after a removal or
+    /// undo. `row` is the visible index of the removed (or previously
+    /// focused) node, where the cursor lands if it was removed.
+    fn after_removal(&mut self, row: Option<usize>) {
+        self.selection.retain_existing(&self.roots);
+        if self.editing.is_some_and(|id| self.node(id).is_none()) {
+            self.cancel_edit();
+        }
+        if self.pending_removal.is_some_and(|id| self.node(id).is_none()) {
+            self.pending_removal = None;
+        }
+
+        // Move the cursor to the row that took the removed node's place
+        if self.focused.is_some_and(|focused| self.node(focused).is_none()) {
+            let rows = self.rows();
+            self.focused = row
+                .and_then(|row| rows.get(row).or(rows.last()))
+                .map(|row| row.node.id());
+        }
+    }
+
+    /// Writes the edited label into the node being edited and leaves edit mode
+    fn commit_edit(&mut self) {
+        if let Some(id) = self.editing
+            && self.node(id).is_some_and(|node| node.label != self.edit_value)
+        {
+            let label = self.edit_value.clone();
+            self.history.begin();
+            self.history.apply(&mut self.roots, Edit::Rename { id, label });
+            self.keep_in_order(id);
+            self.history.commit();
+        }
+        self.cancel_edit();
+    }
+
+    /// Sorts the children of `parent`, or the roots when it is None, by the
+    /// chosen order as one undoable step
+    fn sort(&mut self, parent: Option<NodeId>, recursive: bool) {
+        let edits = sort::edits(&self.roots, parent, recursive, &self.sort_order.by_label());
+        self.history.begin();
+        for edit in edits {
+            self.history.apply(&mut self.roots, edit);
+        }
+        self.history.commit();
+    }
+
+    /// Index for a node added under `parent`: its sorted position in
+    /// keep-sorted mode, otherwise the end
+    fn insert_index(&self, parent: Option<NodeId>, node: &Node) -> usize {
+        if !self.keep_sorted {
+            return usize::MAX;
+        }
+        let siblings = match parent.and_then(|id| self.node(id)) {
+            Some(parent) => &parent.children,
+            None => &self.roots,
+        };
+        sort::position(siblings, node, &self.sort_order.by_label())
+    }
+
+    /// In keep-sorted mode, moves a renamed node to its sorted position
+    /// among its siblings
+    fn keep_in_order(&mut self, id: NodeId) {
+        if !self.keep_sorted {
+            return;
+        }
+        let Some(path) = self.path_of(id) else {
+            return;
+        };
+        let parent = tree::get(&self.roots, &path[..path.len() - 1]).map(Node::id);
+        let Some(node) = self.node(id) else {
+            return;
+        };
+        let index = self.insert_index(parent, node);
+        if index != path[path.len() - 1] {
+            self.history.apply(&mut self.roots, Edit::Move { id, parent, index });
+        }
+    }
+
+    /// Leaves edit mode without touching the node's label
+    fn cancel_edit(&mut self) {
+        self.editing = None;
+        self.edit_value.clear();
+    }
+
+    /// Opens the closed ancestors of a node so it gets a row, as a single
+    /// undoable step
+    fn reveal(&mut self, id: NodeId) -> Task<Message> {
+        let Some(path) = self.path_of(id) else {
+            return Task::none();
+        };
+        let closed: Vec<_> = (1..path.len())
+            .filter_map(|len| tree::get(&self.roots, &path[..len]))
+            .filter(|ancestor| !ancestor.open)
+            .map(|ancestor| ancestor.id())
+            .collect();
+
+        self.history.begin();
+        let loads: Vec<_> = closed.into_iter().map(|id| self.open(id)).collect();
+        self.history.commit();
+        Task::batch(loads)
+    }
+
+    /// Text shown next to the find bar: the match count and which match
+    /// is focused, or why the query is invalid
+    fn find_status(&self) -> String {
+        let search = match &self.search {
+            Ok(search) if search.is_empty() => return String::new(),
+            Ok(search) => search,
+            Err(_) => return "Invalid regex".into(),
+        };
+        let matches = search.matches_in(&self.roots);
+        let current = self
+            .focused
+            .and_then(|id| matches.iter().position(|&found| found == id));
+        match (current, matches.len()) {
+            (_, 0) => "No matches".into(),
+            (Some(index), count) => format!("{} of {count}", index + 1),
+            (None, 1) => "1 match".into(),
+            (None, count) => format!("{count} matches"),
+        }
+    }
+
+    /// Label changes the replace panel would make, or why its pattern is
+    /// invalid
+    fn replace_preview(&self) -> Result<Vec<Rename>, regex::Error> {
+        let replace = if self.replace_regex {
+            Replace::regex(&self.replace_find, &self.replace_with)?
+        } else {
+            Replace::literal(&self.replace_find, &self.replace_with)
+        };
+        let scope = match (self.replace_in_subtree, self.focused) {
+            (false, _) => None,
+            (true, Some(id)) => Some(id),
+            (true, None) => return Ok(Vec::new()),
+        };
+        Ok(replace.preview(&self.roots, scope))
+    }
+
+    /// The rows the tree view shows, narrowed down by the filter
+    fn rows(&self) -> Vec<tree::VisibleRow<'_, Payload>> {
+        self.filter.rows(&self.roots)
+    }
+
+    /// Position of a node among the currently visible nodes. Placeholder
+    /// rows are not counted, so the position still names a node after the
+    /// rows above it finish loading.
+    fn visible_index(&self, id: NodeId) -> Option<usize> {
+        self.rows()
+            .iter()
+            .position(|row| row.node.id() == id)
+    }
+
+    /// Number of rows PageUp/PageDown moves the cursor by: the visible nodes
+    /// that fit in the tree's viewport next to the focused one, counting the
+    /// placeholder rows the tree view draws between them
+    fn page_rows(&self, rows: &[tree::VisibleRow<'_, Payload>], navigation: Navigation) -> usize {
+        let height = self
+            .viewport
+            .map(|viewport| (viewport.bounds().height / DEFAULT_ROW_HEIGHT) as usize)
+            .unwrap_or(DEFAULT_PAGE_ROWS);
+        let Some(current) = self
+            .focused
+            .and_then(|id| rows.iter().position(|row| row.node.id() == id))
+        else {
+            return height;
+        };
+        let indices: Vec<usize> = tree_view::row_indices(rows).collect();
+        let top = indices[current];
+        match navigation {
+            Navigation::PageUp => indices[..current]
+                .iter()
+                .rev()
+                .take_while(|&&index| top - index <= height)
+                .count(),
+            _ => indices[current + 1..]
+                .iter()
+                .take_while(|&&index| index - top <= height)
+                .count(),
+        }
+    }
+
+    /// Scrolls the tree just enough to bring the focused row into view
+    fn scroll_to_focused(&self) -> Task<Message> {
+        let rows = self.rows();
+        let Some(index) = self
+            .focused
+            .and_then(|id| rows.iter().position(|row| row.node.id() == id))
+            .and_then(|index| tree_view::row_indices(&rows).nth(index))
+        else {
+            return Task::none();
+        };
+        let top = index as f32 * DEFAULT_ROW_HEIGHT;
+        let bottom = top + DEFAULT_ROW_HEIGHT;
+        let (offset, height) = self
+            .viewport
+            .map(|viewport| (viewport.absolute_offset().y, viewport.bounds().height))
+            .unwrap_or((0.0, DEFAULT_PAGE_ROWS as f32 * DEFAULT_ROW_HEIGHT));
+
+        let y = if top < offset {
+            top
+        } else if bottom > offset + height {
+            bottom - height
+        } else {
+            return Task::none();
+        };
+        operation::scroll_to(TREE_SCROLLABLE, AbsoluteOffset { x: None, y: Some(y) })
+    }
+    
+    /// Gets a reference to a node at the specified path.
+    /// The first element of the path is the root index, subsequent elements navigate through children.
+    /// Returns None if the path is empty or if any index is out of bounds.
+    #[cfg(test)]
+    fn get_node(&self, path: &[usize]) -> Option<&Node> {
+        tree::get(&self.roots, path)
+    }
+    
+    /// Gets a mutable reference to a node at the specified path.
+    /// The first element of the path is the root index, subsequent elements navigate through children.
+    /// Returns None if the path is empty or if any index is out of bounds.
+    #[cfg(test)]
+    fn get_node_mut(&mut self, path: &[usize]) -> Option<&mut Node> {
+        tree::get_mut(&mut self.roots, path)
+    }
+
+    /// Gets a reference to the node with the given id, wherever it currently is.
+    fn node(&self, id: NodeId) -> Option<&Node> {
+        tree::get(&self.roots, &self.path_of(id)?)
+    }
+
+    fn node_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+        let path = self.path_of(id)?;
+        tree::get_mut(&mut self.roots, &path)
+    }
+
+    /// Resolves a node id to its current path, or None if the node no longer exists.
+    /// Looks the id up in `index`, rebuilding it when the tree has changed since.
+    fn path_of(&self, id: NodeId) -> Option<Vec<usize>> {
+        if let Some(path) = self.index.borrow().path(&self.roots, id) {
+            return Some(path.to_vec());
+        }
+        let index = tree::Index::new(&self.roots);
+        let path = index.path(&self.roots, id).map(<[usize]>::to_vec);
+        self.index.replace(index);
+        path
+    }
+
+    /// Resolves a path to the id of the node currently at that position.
+    #[cfg(test)]
+    fn id_at(&self, path: &[usize]) -> Option<NodeId> {
+        self.get_node(path).map(Node::id)
+    }
+
+    fn view(&self) -> Element<'_, Message> {
+        let toolbar = row![
+            button("Open").on_press(Message::Open),
+            button("Open folder").on_press(Message::OpenFolder),
+            button("Save").on_press(Message::Save),
+            button("Save As").on_press(Message::SaveAs),
+            button("Add root").on_press(Message::AddRoot),
+            button("Replace").on_press(Message::ToggleReplace),
+            pick_list(Order::ALL, Some(self.sort_order), Message::SortOrderChanged),
+            button("Sort").on_press(Message::Sort { recursive: false }),
+            button("Sort all levels").on_press(Message::Sort { recursive: true }),
+            checkbox(self.keep_sorted)
+                .label("Keep sorted")
+                .on_toggle(Message::KeepSorted),
+            checkbox(self.show_checks)
+                .label("Checkboxes")
+                .on_toggle(Message::ShowChecks),
+        ]
+        .push(
+            self.show_checks
+                .then(|| text(format!("{} checked", self.checked_count()))),
+        )
+        .push(
+            self.show_checks
+                .then(|| button("Copy checked").on_press(Message::CopyChecked)),
+        )
+        .push(self.error.as_deref().map(text))
+        .spacing(8)
+        .padding(4);
+
+        let confirmation = self.pending_removal.and_then(|id| self.node(id)).map(|node| {
+            row![
+                text(format!(
+                    "Remove \"{}\" and its {} descendants?",
+                    node.label,
+                    node.descendant_count()
+                ))
+                .width(Length::Fill),
+                button("Remove").on_press(Message::ConfirmRemove),
+                button("Cancel").on_press(Message::CancelRemove),
+            ]
+            .spacing(8)
+            .padding(4)
+        });
+
+        let tree = scrollable(
+            TreeView::filtered(&self.roots, &self.filter, |node| Decoration {
+                icon: node.data.as_ref().map(|entry| entry.icon(node.open)),
+                check: self.check_states.get(&node.id()).copied(),
+                ..Decoration::default()
+            })
+                .on_toggle(Message::Toggle)
+                .on_select(Message::Select)
+                .selection(&self.selection)
+                .focused(self.focused)
+                .on_rename(Message::StartEdit)
+                .on_add(Message::AddChild)
+                .on_remove(Message::RequestRemove)
+                .on_drop(Message::Drop)
+                .on_retry(Message::Retry)
+                .on_check(Message::ToggleCheck)
+                .editing(self.editing, &self.edit_value)
+                .on_edit(Message::EditLabel)
+                .on_submit(Message::FinishEdit)
+                .on_cancel(Message::CancelEdit)
+                .on_blur(Message::EditBlurred),
+        )
+        .id(TREE_SCROLLABLE)
+        .on_scroll(Message::Scrolled)
+        .height(Length::Fill);
+
+        let filter = row![
+            text_input("Filter", self.filter.query())
+                .on_input(Message::FilterChanged)
+                .width(Length::Fill),
+        ]
+        .push(
+            (!self.filter.is_empty())
+                .then(|| button("Clear").on_press(Message::FilterChanged(String::new()))),
+        )
+        .spacing(8)
+        .padding(4);
+
+        let find = row![
+            text_input("Find", &self.find)
+                .on_input(Message::FindChanged)
+                .on_submit(Message::Find(Direction::Forward))
+                .width(Length::Fill),
+            pick_list(search::Mode::ALL, Some(self.find_mode), Message::FindModeChanged),
+            text(self.find_status()),
+            button("Previous").on_press(Message::Find(Direction::Backward)),
+            button("Next").on_press(Message::Find(Direction::Forward)),
+        ]
+        .spacing(8)
+        .padding(4)
+        .align_y(iced::Center);
+
+        let replace = self.replacing.then(|| self.replace_panel());
+
+        column![toolbar]
+            .push(confirmation)
+            .push(find)
+            .push(replace)
+            .push(filter)
+            .push(tree)
+            .into()
+    }
+
+    /// Find-and-replace fields with a preview of the labels they change
+    fn replace_panel(&self) -> Element<'_, Message> {
+        let fields = row![
+            text_input("Find", &self.replace_find)
+                .on_input(Message::ReplaceFindChanged)
+                .width(Length::Fill),
+            text_input("Replace with", &self.replace_with)
+                .on_input(Message::ReplaceWithChanged)
+                .width(Length::Fill),
+            checkbox(self.replace_regex)
+                .label("Regex")
+                .on_toggle(Message::ReplaceRegex),
+            checkbox(self.replace_in_subtree)
+                .label("Focused subtree only")
+                .on_toggle(Message::ReplaceInSubtree),
+        ]
+        .spacing(8)
+        .align_y(iced::Center);
+
+        let (preview, renames) = match self.replace_preview() {
+            Ok(renames) => {
+                let mut preview = column(
+                    renames
+                        .iter()
+                        .take(PREVIEW_ROWS)
+                        .map(|rename| text(format!("{} → {}", rename.from, rename.to)).into()),
+                );
+                if renames.len() > PREVIEW_ROWS {
+                    preview = preview.push(text(format!(
+                        "and {} more",
+                        renames.len() - PREVIEW_ROWS
+                    )));
+                }
+                (preview, renames.len())
+            }
+            Err(_) => (column![text("Invalid regex")], 0),
+        };
+
+        let actions = row![
+            text(format!("{renames} labels change")).width(Length::Fill),
+            button("Replace all").on_press_maybe((renames > 0).then_some(Message::ReplaceAll)),
+            button("Close").on_press(Message::ToggleReplace),
+        ]
+        .spacing(8)
+        .align_y(iced::Center);
+
+        column![fields, preview, actions]
+            .spacing(8)
+            .padding(4)
+            .into()
+    }
+}
+
+/// Creates a node that does not stand for a file
+fn plain(label: &str, open: bool, children: Vec<Node>) -> Node {
+    Node::with_data(label, None, open, children)
+}
+
+/// Asks for a folder and reads it as a tree, off the async executor
+async fn open_folder() -> Result<Node, Error> {
+    let handle = rfd::AsyncFileDialog::new()
+        .set_title("Open folder")
+        .pick_folder()
+        .await
+        .ok_or(Error::DialogClosed)?;
+
+    let path = handle.path().to_owned();
+    tokio::task::spawn_blocking(move || filesystem::open(&path))
+        .await
+        .map_err(|error| Error::Io(io::Error::from(error).kind()))?
+        .map_err(|error| Error::Io(error.kind()))
+}
+
+/// File formats the tree can be stored in, picked by extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Json,
+    Opml,
+    /// Indented plain-text outline
+    Text,
+    /// Nested Markdown bullet list
+    Markdown,
+}
+
+impl Format {
+    /// OPML for `.opml`, outlines for `.txt` and `.md`, JSON for anything else
+    fn of(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("opml") => Format::Opml,
+            Some("txt") => Format::Text,
+            Some("md" | "markdown") => Format::Markdown,
+            _ => Format::Json,
+        }
+    }
+}
+
+/// Asks for a JSON, OPML or outline file and loads the tree in it
+async fn open_file() -> Result<(PathBuf, Vec<Node>), Error> {
+    let handle = rfd::AsyncFileDialog::new()
+        .set_title("Open tree")
+        .add_filter("Tree", &["json", "opml", "txt", "md"])
+        .add_filter("JSON", &["json"])
+        .add_filter("OPML", &["opml"])
+        .add_filter("Text outline", &["txt"])
+        .add_filter("Markdown", &["md", "markdown"])
+        .pick_file()
+        .await
+        .ok_or(Error::DialogClosed)?;
+
+    load_file(handle.path().to_owned()).await
+}
+
+async fn load_file(path: PathBuf) -> Result<(PathBuf, Vec<Node>), Error> {
+    let contents = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|error| Error::Io(error.kind()))?;
+    let roots = parse_tree(&contents, Format::of(&path))?;
+
+    Ok((path, roots))
+}
+
+/// Parses a file written by [`save_file`], or an outline from another tool
+fn parse_tree(contents: &str, format: Format) -> Result<Vec<Node>, Error> {
+    match format {
+        Format::Json => {
+            serde_json::from_str(contents).map_err(|error| Error::Malformed(error.to_string()))
+        }
+        Format::Opml => opml::read(contents).map_err(|error| Error::Malformed(error.to_string())),
+        Format::Text => outline::parse(contents, &outline::Options::default())
+            .map_err(|error| Error::Malformed(error.to_string())),
+        Format::Markdown => outline::parse(contents, &outline::Options::markdown())
+            .map_err(|error| Error::Malformed(error.to_string())),
+    }
+}
+
+/// Serializes the tree for a file at `path`
+fn serialize_tree(roots: &[Node], path: &Path) -> Result<String, Error> {
+    Ok(match Format::of(path) {
+        Format::Json => serde_json::to_string_pretty(roots)
+            .map_err(|error| Error::Io(io::Error::from(error).kind()))?,
+        Format::Opml => {
+            let title = path.file_stem().unwrap_or_default().to_string_lossy();
+            opml::write(roots, &title)
+        }
+        Format::Text => outline::write(roots, &outline::Options::default()),
+        Format::Markdown => outline::write(roots, &outline::Options::markdown()),
+    })
+}
+
+/// Writes the tree to `path`, or to a file picked in a dialog when it is None
+async fn save_file(path: Option<PathBuf>, roots: Vec<Node>) -> Result<PathBuf, Error> {
+    let path = match path {
+        Some(path) => path,
+        None => rfd::AsyncFileDialog::new()
+            .set_title("Save tree")
+            .add_filter("JSON", &["json"])
+            .add_filter("OPML", &["opml"])
+            .add_filter("Text outline", &["txt"])
+            .add_filter("Markdown", &["md"])
+            .set_file_name("tree.json")
+            .save_file()
+            .await
+            .map(|handle| handle.path().to_owned())
+            .ok_or(Error::DialogClosed)?,
+    };
+
+    let contents = serialize_tree(&roots, &path)?;
+    tokio::fs::write(&path, contents)
+        .await
+        .map_err(|error| Error::Io(error.kind()))?;
+
+    Ok(path)
+}
+
+/// The system clipboard, kept open because on some platforms the copying
+/// application has to serve what it copied
+static SYSTEM_CLIPBOARD: Mutex<Option<arboard::Clipboard>> = Mutex::new(None);
+
+/// Runs `f` on the system clipboard, opening it on first use
+fn system_clipboard<R>(
+    f: impl FnOnce(&mut arboard::Clipboard) -> Result<R, arboard::Error>,
+) -> Result<R, arboard::Error> {
+    let mut clipboard = SYSTEM_CLIPBOARD
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    let clipboard = match &mut *clipboard {
+        Some(clipboard) => clipboard,
+        None => clipboard.insert(arboard::Clipboard::new()?),
+    };
+    f(clipboard)
+}
+
+/// Writes both flavours of a copy to the system clipboard. A failure leaves
+/// the clipboard as it was, like copying in iced's text inputs.
+async fn write_clipboard(contents: Contents) {
+    let _ = system_clipboard(|clipboard| match contents.html {
+        Some(html) => clipboard.set().html(html, Some(contents.text)),
+        None => clipboard.set_text(contents.text),
+    });
+}
+
+/// Reads the system clipboard's text and, when it offers one, its HTML
+async fn read_clipboard() -> Option<Contents> {
+    system_clipboard(|clipboard| {
+        let text = clipboard.get_text()?;
+        let html = clipboard.get().html().ok();
+        Ok(Contents { text, html })
+    })
+    .ok()
+}
+
+impl Default for TreeDemo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced_tree::filesystem::Kind;
+
+    /// Creates an empty file under `dir` along with the folders leading to it
+    fn touch(dir: &std::path::Path, relative: &str) -> PathBuf {
+        let path = dir.join(relative);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "").unwrap();
+        path
+    }
+
+    #[test]
+    fn test_tree_demo_new() {
+        let demo = TreeDemo::new();
+        
+        assert_eq!(demo.roots.len(), 2);
+        assert_eq!(demo.roots[0].label, "Root 1");
+        assert!(demo.roots[0].open);
+        assert_eq!(demo.roots[0].children.len(), 2);
+        assert_eq!(demo.roots[1].label, "Root 2");
+        assert_eq!(demo.editing, None);
+        assert_eq!(demo.edit_value, "");
+    }
+
+    #[test]
+    fn test_tree_demo_default() {
+        let demo = TreeDemo::default();
+        
+        assert_eq!(demo.roots.len(), 2);
+        assert_eq!(demo.roots[0].label, "Root 1");
+        assert_eq!(demo.editing, None);
+    }
+
+    #[test]
+    fn test_toggle_node() {
+        let mut demo = TreeDemo::new();
+        let path = vec![0, 0]; // First root's first child
+        
+        // Get initial state
+        let initially_open = demo.get_node(&path).unwrap().open;
+        
+        // Toggle the node
+        let _ = demo.update(Message::Toggle(demo.id_at(&path).unwrap()));
+        
+        // Verify the state changed
+        let is_open_after_toggle = demo.get_node(&path).unwrap().open;
+        assert_eq!(is_open_after_toggle, !initially_open);
+    }
+
+    #[test]
+    fn test_toggle_root() {
+        let mut demo = TreeDemo::new();
+        let path = vec![0]; // First root node
+        
+        assert!(demo.roots[0].open);
+        
+        let _ = demo.update(Message::Toggle(demo.id_at(&path).unwrap()));
+        assert!(!demo.roots[0].open);
+        
+        let _ = demo.update(Message::Toggle(demo.id_at(&path).unwrap()));
+        assert!(demo.roots[0].open);
+    }
+
+    #[test]
+    fn test_start_edit() {
+        let mut demo = TreeDemo::new();
+        let path = vec![0, 0]; // First root's first child
+        
+        let _ = demo.update(Message::StartEdit(demo.id_at(&path).unwrap()));
+        
+        assert_eq!(demo.editing, demo.id_at(&path));
+        assert_eq!(demo.edit_value, "Branch 1.1");
+    }
+
+    #[test]
+    fn test_edit_label() {
+        let mut demo = TreeDemo::new();
+        
+        let _ = demo.update(Message::EditLabel("New Value".to_string()));
+        
+        assert_eq!(demo.edit_value, "New Value");
+    }
+
+    #[test]
+    fn test_finish_edit() {
+        let mut demo = TreeDemo::new();
+        let path = vec![0, 0]; // First root's first child
+        
+        // Start editing
+        let _ = demo.update(Message::StartEdit(demo.id_at(&path).unwrap()));
+        assert_eq!(demo.edit_value, "Branch 1.1");
+        
+        // Change the value
+        let _ = demo.update(Message::EditLabel("Updated Branch".to_string()));
+        
+        // Finish editing
+        let _ = demo.update(Message::FinishEdit);
+        
+        // Verify the label was updated
+        assert_eq!(demo.get_node(&path).unwrap().label, "Updated Branch");
+        assert_eq!(demo.editing, None);
+        assert_eq!(demo.edit_value, "");
+    }
+
+    #[test]
+    fn test_finish_edit_without_start() {
+        let mut demo = TreeDemo::new();
+        
+        // Try to finish edit without starting
+        let _ = demo.update(Message::FinishEdit);
+        
+        // Should not panic and state should remain unchanged
+        assert_eq!(demo.editing, None);
+        assert_eq!(demo.edit_value, "");
+    }
+
+    #[test]
+    fn test_get_node_root() {
+        let demo = TreeDemo::new();
+        
+        let node = demo.get_node(&[0]);
+        assert!(node.is_some());
+        assert_eq!(node.unwrap().label, "Root 1");
+    }
+
+    #[test]
+    fn test_get_node_first_child() {
+        let demo = TreeDemo::new();
+        
+        let node = demo.get_node(&[0, 0]);
+        assert!(node.is_some());
+        assert_eq!(node.unwrap().label, "Branch 1.1");
+    }
+
+    #[test]
+    fn test_get_node_nested() {
+        let demo = TreeDemo::new();
+        
+        let node = demo.get_node(&[0, 0, 0]);
+        assert!(node.is_some());
+        assert_eq!(node.unwrap().label, "Leaf 1.1.1");
+    }
+
+    #[test]
+    fn test_get_node_invalid_path() {
+        let demo = TreeDemo::new();
+        
+        // Path that doesn't exist
+        let node = demo.get_node(&[99]);
+        assert!(node.is_none());
+        
+        // Empty path should also return None
+        let node = demo.get_node(&[]);
+        assert!(node.is_none());
+    }
+
+    #[test]
+    fn test_get_node_mut() {
+        let mut demo = TreeDemo::new();
+        
+        // Modify a node directly
+        if let Some(node) = demo.get_node_mut(&[0, 0]) {
+            node.label = "Modified".to_string();
+        }
+        
+        assert_eq!(demo.get_node(&[0, 0]).unwrap().label, "Modified");
+    }
+
+    #[test]
+    fn test_multiple_edits() {
+        let mut demo = TreeDemo::new();
+        
+        // Edit first root's first child
+        let _ = demo.update(Message::StartEdit(demo.id_at(&[0, 0]).unwrap()));
+        let _ = demo.update(Message::EditLabel("Branch A".to_string()));
+        let _ = demo.update(Message::FinishEdit);
+        
+        // Edit second root's first child
+        let _ = demo.update(Message::StartEdit(demo.id_at(&[1, 0]).unwrap()));
+        let _ = demo.update(Message::EditLabel("Branch B".to_string()));
+        let _ = demo.update(Message::FinishEdit);
+        
+        assert_eq!(demo.get_node(&[0, 0]).unwrap().label, "Branch A");
+        assert_eq!(demo.get_node(&[1, 0]).unwrap().label, "Branch B");
+    }
+
+    #[test]
+    fn test_edit_deep_node() {
+        let mut demo = TreeDemo::new();
+        let path = vec![0, 0, 1]; // First root, first child, second leaf
+        
+        let _ = demo.update(Message::StartEdit(demo.id_at(&path).unwrap()));
+        let _ = demo.update(Message::EditLabel("Deep Leaf".to_string()));
+        let _ = demo.update(Message::FinishEdit);
+        
+        assert_eq!(demo.get_node(&path).unwrap().label, "Deep Leaf");
+    }
+
+    #[test]
+    fn test_multiple_roots() {
+        let demo = TreeDemo::new();
+        
+        // Verify we have multiple roots
+        assert_eq!(demo.roots.len(), 2);
+        
+        // Verify first root
+        let root1 = demo.get_node(&[0]);
+        assert!(root1.is_some());
+        assert_eq!(root1.unwrap().label, "Root 1");
+        
+        // Verify second root
+        let root2 = demo.get_node(&[1]);
+        assert!(root2.is_some());
+        assert_eq!(root2.unwrap().label, "Root 2");
+    }
+
+    #[test]
+    fn test_toggle_different_roots() {
+        let mut demo = TreeDemo::new();
+        
+        // Toggle first root
+        let path1 = vec![0];
+        assert!(demo.roots[0].open);
+        let _ = demo.update(Message::Toggle(demo.id_at(&path1).unwrap()));
+        assert!(!demo.roots[0].open);
+        
+        // Toggle second root
+        let path2 = vec![1];
+        assert!(demo.roots[1].open);
+        let _ = demo.update(Message::Toggle(demo.id_at(&path2).unwrap()));
+        assert!(!demo.roots[1].open);
+        
+        // Verify first root is still closed
+        assert!(!demo.roots[0].open);
+    }
+
+    #[test]
+    fn test_edit_nodes_in_different_roots() {
+        let mut demo = TreeDemo::new();
+        
+        // Edit a node in the first root
+        let _ = demo.update(Message::StartEdit(demo.id_at(&[0, 0]).unwrap()));
+        let _ = demo.update(Message::EditLabel("Modified Root 1 Branch".to_string()));
+        let _ = demo.update(Message::FinishEdit);
+        
+        // Edit a node in the second root
+        let _ = demo.update(Message::StartEdit(demo.id_at(&[1, 1]).unwrap()));
+        let _ = demo.update(Message::EditLabel("Modified Root 2 Branch".to_string()));
+        let _ = demo.update(Message::FinishEdit);
+        
+        // Verify both edits were applied
+        assert_eq!(demo.get_node(&[0, 0]).unwrap().label, "Modified Root 1 Branch");
+        assert_eq!(demo.get_node(&[1, 1]).unwrap().label, "Modified Root 2 Branch");
+    }
+
+    #[test]
+    fn test_get_node_second_root() {
+        let demo = TreeDemo::new();
+        
+        // Get second root
+        let root2 = demo.get_node(&[1]);
+        assert!(root2.is_some());
+        assert_eq!(root2.unwrap().label, "Root 2");
+        
+        // Get child of second root
+        let child = demo.get_node(&[1, 0]);
+        assert!(child.is_some());
+        assert_eq!(child.unwrap().label, "Branch 2.1");
+    }
+
+    #[test]
+    fn test_add_child_to_root() {
+        let mut demo = TreeDemo::new();
+        let path = vec![0]; // First root
+        
+        let initial_children = demo.get_node(&path).unwrap().children.len();
+        
+        let _ = demo.update(Message::AddChild(demo.id_at(&path).unwrap()));
+        
+        let node = demo.get_node(&path).unwrap();
+        assert_eq!(node.children.len(), initial_children + 1);
+        assert_eq!(node.children[initial_children].label, "New Node");
+        // Verify the parent was opened
+        assert!(node.open);
+    }
+
+    #[test]
+    fn test_add_child_to_branch() {
+        let mut demo = TreeDemo::new();
+        let path = vec![0, 0]; // First root's first child
+        
+        let initial_children = demo.get_node(&path).unwrap().children.len();
+        
+        let _ = demo.update(Message::AddChild(demo.id_at(&path).unwrap()));
+        
+        let node = demo.get_node(&path).unwrap();
+        assert_eq!(node.children.len(), initial_children + 1);
+        assert_eq!(node.children[initial_children].label, "New Node");
+        assert!(node.open);
+    }
+
+    #[test]
+    fn test_add_multiple_children() {
+        let mut demo = TreeDemo::new();
+        let path = vec![1, 0]; // Second root's first child
+        
+        let initial_children = demo.get_node(&path).unwrap().children.len();
+        
+        let _ = demo.update(Message::AddChild(demo.id_at(&path).unwrap()));
+        let _ = demo.update(Message::AddChild(demo.id_at(&path).unwrap()));
+        let _ = demo.update(Message::AddChild(demo.id_at(&path).unwrap()));
+        
+        let node = demo.get_node(&path).unwrap();
+        assert_eq!(node.children.len(), initial_children + 3);
+    }
+
+    #[test]
+    fn test_add_child_to_leaf() {
+        let mut demo = TreeDemo::new();
+        let path = vec![0, 0, 0]; // A leaf node
+        
+        // Verify it starts with no children
+        assert_eq!(demo.get_node(&path).unwrap().children.len(), 0);
+        
+        let _ = demo.update(Message::AddChild(demo.id_at(&path).unwrap()));
+        
+        let node = demo.get_node(&path).unwrap();
+        assert_eq!(node.children.len(), 1);
+        assert_eq!(node.children[0].label, "New Node");
+    }
+
+    #[test]
+    fn test_remove_child() {
+        let mut demo = TreeDemo::new();
+        // First root has 2 children initially
+        let parent_path = vec![0];
+        let initial_count = demo.get_node(&parent_path).unwrap().children.len();
+        
+        // Remove the first child
+        let _ = demo.update(Message::RemoveChild(demo.id_at(&[0, 0]).unwrap()));
+        
+        let node = demo.get_node(&parent_path).unwrap();
+        assert_eq!(node.children.len(), initial_count - 1);
+    }
+
+    #[test]
+    fn test_remove_child_from_branch() {
+        let mut demo = TreeDemo::new();
+        let path = vec![0, 0]; // First root's first child
+        let initial_count = demo.get_node(&path).unwrap().children.len();
+        
+        // Remove its first child
+        let _ = demo.update(Message::RemoveChild(demo.id_at(&[0, 0, 0]).unwrap()));
+        
+        let node = demo.get_node(&path).unwrap();
+        assert_eq!(node.children.len(), initial_count - 1);
+    }
+
+    #[test]
+    fn test_remove_nonexistent_child() {
+        let mut demo = TreeDemo::new();
+        let parent_path = vec![0];
+        let initial_count = demo.get_node(&parent_path).unwrap().children.len();
+        
+        // Try to remove a node that is not part of the tree
+        let _ = demo.update(Message::RemoveChild(plain("Detached", false, vec![]).id()));
+        
+        // Count should remain the same
+        let node = demo.get_node(&parent_path).unwrap();
+        assert_eq!(node.children.len(), initial_count);
+    }
+
+    #[test]
+    fn test_remove_child_with_short_path() {
+        let mut demo = TreeDemo::new();
+        let initial_roots = demo.roots.len();
+        
+        // Try to remove with a path that's too short (just root)
+        let _ = demo.update(Message::RemoveChild(demo.id_at(&[0]).unwrap()));
+        
+        // Should not remove root nodes
+        assert_eq!(demo.roots.len(), initial_roots);
+    }
+
+    #[test]
+    fn test_add_and_remove_child_sequence() {
+        let mut demo = TreeDemo::new();
+        let path = vec![1, 0];
+        
+        let initial_count = demo.get_node(&path).unwrap().children.len();
+        
+        // Add a child
+        let _ = demo.update(Message::AddChild(demo.id_at(&path).unwrap()));
+        assert_eq!(demo.get_node(&path).unwrap().children.len(), initial_count + 1);
+        
+        // Remove the newly added child
+        let _ = demo.update(Message::RemoveChild(demo.id_at(&[1, 0, initial_count]).unwrap()));
+        assert_eq!(demo.get_node(&path).unwrap().children.len(), initial_count);
+    }
+
+    #[test]
+    fn test_remove_all_children() {
+        let mut demo = TreeDemo::new();
+        let path = vec![0, 0]; // First root's first child (has 2 children initially)
+        
+        let child_count = demo.get_node(&path).unwrap().children.len();
+        
+        // Remove all children
+        for _ in 0..child_count {
+            let _ = demo.update(Message::RemoveChild(demo.id_at(&[0, 0, 0]).unwrap()));
+        }
+        
+        let node = demo.get_node(&path).unwrap();
+        assert_eq!(node.children.len(), 0);
+    }
+
+    #[test]
+    fn test_add_child_updates_tree_structure() {
+        let mut demo = TreeDemo::new();
+        let path = vec![0, 1]; // First root's second child
+        
+        let initial_count = demo.get_node(&path).unwrap().children.len();
+        
+        let _ = demo.update(Message::AddChild(demo.id_at(&path).unwrap()));
+        
+        // Verify we can access the new child (it's added at the end)
+        let new_child_path = vec![0, 1, initial_count];
+        let new_child = demo.get_node(&new_child_path);
+        assert!(new_child.is_some());
+        assert_eq!(new_child.unwrap().label, "New Node");
+    }
+
+    #[test]
+    fn test_add_child_to_closed_node_opens_it() {
+        let mut demo = TreeDemo::new();
+        let path = vec![0, 0]; // First root's first child
+        
+        // Close the node first
+        if demo.get_node(&path).unwrap().open {
+            let _ = demo.update(Message::Toggle(demo.id_at(&path).unwrap()));
+        }
+        assert!(!demo.get_node(&path).unwrap().open);
+        
+        // Add a child
+        let _ = demo.update(Message::AddChild(demo.id_at(&path).unwrap()));
+        
+        // Verify the node was opened
+        assert!(demo.get_node(&path).unwrap().open);
+    }
+
+    #[test]
+    fn test_edit_newly_added_child() {
+        let mut demo = TreeDemo::new();
+        let parent_path = vec![0, 0];
+        
+        // Add a new child
+        let _ = demo.update(Message::AddChild(demo.id_at(&parent_path).unwrap()));
+        
+        let child_count = demo.get_node(&parent_path).unwrap().children.len();
+        let new_child_path = vec![0, 0, child_count - 1];
+        
+        // Edit the new child
+        let _ = demo.update(Message::StartEdit(demo.id_at(&new_child_path).unwrap()));
+        let _ = demo.update(Message::EditLabel("Edited New Node".to_string()));
+        let _ = demo.update(Message::FinishEdit);
+        
+        assert_eq!(demo.get_node(&new_child_path).unwrap().label, "Edited New Node");
+    }
+
+    #[test]
+    fn test_edit_survives_sibling_removal() {
+        let mut demo = TreeDemo::new();
+        let target = demo.id_at(&[0, 1]).unwrap();
+
+        let _ = demo.update(Message::StartEdit(target));
+        let _ = demo.update(Message::EditLabel("Still Branch 1.2".to_string()));
+
+        // Removing the preceding sibling shifts the target's path to [0, 0]
+        let _ = demo.update(Message::RemoveChild(demo.id_at(&[0, 0]).unwrap()));
+        let _ = demo.update(Message::FinishEdit);
+
+        assert_eq!(demo.path_of(target), Some(vec![0, 0]));
+        assert_eq!(demo.node(target).unwrap().label, "Still Branch 1.2");
+    }
+
+    #[test]
+    fn test_select_and_extend() {
+        let mut demo = TreeDemo::new();
+        let first = demo.id_at(&[0, 0]).unwrap();
+        let last = demo.id_at(&[1, 1]).unwrap();
+
+        let _ = demo.update(Message::Select(first, SelectMode::Replace));
+        assert!(demo.selection.is_selected(first));
+
+        // Visible rows from Branch 1.1 to Branch 2.2
+        let _ = demo.update(Message::Select(last, SelectMode::Extend));
+        assert_eq!(demo.selection.len(), 5);
+
+        let _ = demo.update(Message::Select(first, SelectMode::Toggle));
+        assert!(!demo.selection.is_selected(first));
+        assert_eq!(demo.selection.len(), 4);
+    }
+
+    #[test]
+    fn test_select_all() {
+        let mut demo = TreeDemo::new();
+
+        let _ = demo.update(Message::SelectAll);
+
+        assert_eq!(demo.selection.len(), tree::visible_rows(&demo.roots).len());
+    }
+
+    #[test]
+    fn test_filter_reveals_matches_without_expanding() {
+        let mut demo = TreeDemo::new();
+        let branch = demo.id_at(&[0, 1]).unwrap();
+
+        let _ = demo.update(Message::FilterChanged("1.2".into()));
+        let labels: Vec<_> = demo.rows().iter().map(|row| row.node.label.as_str()).collect();
+        assert_eq!(
+            labels,
+            ["Root 1", "Branch 1.1", "Leaf 1.1.2", "Branch 1.2", "Leaf 1.2.1"]
+        );
+
+        let _ = demo.update(Message::SelectAll);
+        assert_eq!(demo.selection.len(), 5);
+
+        // Left leaves a node that is only expanded by the filter alone
+        let _ = demo.update(Message::Navigate(Navigation::End));
+        let _ = demo.update(Message::Navigate(Navigation::Left));
+        assert_eq!(demo.focused, Some(branch));
+        let _ = demo.update(Message::Navigate(Navigation::Left));
+        assert_eq!(demo.focused, demo.id_at(&[0]));
+
+        let _ = demo.update(Message::FilterChanged(String::new()));
+        assert!(!demo.get_node(&[0, 0]).unwrap().open);
+        assert!(!demo.get_node(&[0, 1]).unwrap().open);
+        assert_eq!(demo.rows().len(), 6);
+        assert!(!demo.history.can_undo());
+    }
+
+    #[test]
+    fn test_toggle_leaves_filter_expanded_rows_alone() {
+        let mut demo = TreeDemo::new();
+        let branch = demo.id_at(&[0, 0]).unwrap();
+        let _ = demo.update(Message::FilterChanged("1.1.2".into()));
+        assert!(demo.rows()[1].open);
+
+        let _ = demo.update(Message::Toggle(branch));
+        assert!(demo.rows()[1].open);
+        assert!(!demo.history.can_undo());
+
+        let _ = demo.update(Message::FilterChanged(String::new()));
+        assert!(!demo.node(branch).unwrap().open);
+    }
+
+    #[test]
+    fn test_find_reveals_matches_in_closed_nodes() {
+        let mut demo = TreeDemo::new();
+        let first = demo.id_at(&[0, 0, 0]).unwrap();
+        let second = demo.id_at(&[0, 0, 1]).unwrap();
+
+        let _ = demo.update(Message::FindChanged("leaf 1.1".into()));
+        assert_eq!(demo.find_status(), "2 matches");
+
+        let _ = demo.update(Message::Find(Direction::Forward));
+        assert_eq!(demo.focused, Some(first));
+        assert!(demo.selection.is_selected(first));
+        assert!(demo.get_node(&[0, 0]).unwrap().open);
+        assert_eq!(demo.find_status(), "1 of 2");
+
+        let _ = demo.update(Message::Find(Direction::Forward));
+        assert_eq!(demo.focused, Some(second));
+        let _ = demo.update(Message::Find(Direction::Forward));
+        assert_eq!(demo.focused, Some(first));
+        let _ = demo.update(Message::Find(Direction::Backward));
+        assert_eq!(demo.focused, Some(second));
+
+        // Revealing is a single step that undo closes again
+        let _ = demo.update(Message::Undo);
+        assert!(!demo.get_node(&[0, 0]).unwrap().open);
+        assert!(!demo.history.can_undo());
+
+        let _ = demo.update(Message::FindModeChanged(search::Mode::Fuzzy));
+        let _ = demo.update(Message::FindChanged("l121".into()));
+        assert_eq!(demo.find_status(), "1 match");
+
+        let _ = demo.update(Message::FindModeChanged(search::Mode::Regex));
+        let _ = demo.update(Message::FindChanged("(".into()));
+        assert_eq!(demo.find_status(), "Invalid regex");
+        let _ = demo.update(Message::Find(Direction::Forward));
+        assert_eq!(demo.focused, Some(second));
+    }
+
+    #[test]
+    fn test_replace_all_is_one_undoable_step() {
+        let mut demo = TreeDemo::new();
+
+        let _ = demo.update(Message::ReplaceFindChanged("Leaf".into()));
+        let _ = demo.update(Message::ReplaceWithChanged("Item".into()));
+        let preview = demo.replace_preview().unwrap();
+        assert_eq!(preview.len(), 3);
+        assert_eq!(preview[0].to, "Item 1.1.1");
+        // The preview leaves the tree alone
+        assert_eq!(demo.get_node(&[0, 0, 0]).unwrap().label, "Leaf 1.1.1");
+
+        let _ = demo.update(Message::ReplaceAll);
+        assert_eq!(demo.get_node(&[0, 0, 1]).unwrap().label, "Item 1.1.2");
+        assert_eq!(demo.get_node(&[0, 1, 0]).unwrap().label, "Item 1.2.1");
+
+        let _ = demo.update(Message::Undo);
+        assert_eq!(demo.get_node(&[0, 0, 1]).unwrap().label, "Leaf 1.1.2");
+        assert_eq!(demo.get_node(&[0, 1, 0]).unwrap().label, "Leaf 1.2.1");
+        assert!(!demo.history.can_undo());
+    }
+
+    #[test]
+    fn test_replace_regex_in_focused_subtree() {
+        let mut demo = TreeDemo::new();
+        let _ = demo.update(Message::Select(demo.id_at(&[0, 1]).unwrap(), SelectMode::Replace));
+
+        let _ = demo.update(Message::ReplaceRegex(true));
+        let _ = demo.update(Message::ReplaceInSubtree(true));
+        let _ = demo.update(Message::ReplaceFindChanged(r"(\w+) (\d)\.(\d)".into()));
+        let _ = demo.update(Message::ReplaceWithChanged("$1 $3.$2".into()));
+        let _ = demo.update(Message::ReplaceAll);
+
+        assert_eq!(demo.get_node(&[0, 1]).unwrap().label, "Branch 2.1");
+        assert_eq!(demo.get_node(&[0, 1, 0]).unwrap().label, "Leaf 2.1.1");
+        assert_eq!(demo.get_node(&[0, 0]).unwrap().label, "Branch 1.1");
+
+        let _ = demo.update(Message::ReplaceFindChanged("(".into()));
+        assert!(demo.replace_preview().is_err());
+    }
+
+    /// Renames the node at `path` the way the label editor does
+    fn rename(demo: &mut TreeDemo, path: &[usize], label: &str) {
+        let id = demo.id_at(path).unwrap();
+        let _ = demo.update(Message::StartEdit(id));
+        let _ = demo.update(Message::EditLabel(label.into()));
+        let _ = demo.update(Message::FinishEdit);
+    }
+
+    #[test]
+    fn test_sort_focused_children_in_natural_order() {
+        let mut demo = TreeDemo::new();
+        rename(&mut demo, &[1, 0], "Item 10");
+        rename(&mut demo, &[1, 1], "Item 9");
+        let _ = demo.update(Message::Select(demo.id_at(&[1]).unwrap(), SelectMode::Replace));
+
+        let _ = demo.update(Message::SortOrderChanged(Order::Natural));
+        let _ = demo.update(Message::Sort { recursive: false });
+        assert_eq!(demo.get_node(&[1, 0]).unwrap().label, "Item 9");
+        assert_eq!(demo.get_node(&[1, 1]).unwrap().label, "Item 10");
+
+        let _ = demo.update(Message::Undo);
+        assert_eq!(demo.get_node(&[1, 0]).unwrap().label, "Item 10");
+    }
+
+    #[test]
+    fn test_keep_sorted_places_added_and_renamed_nodes() {
+        let mut demo = TreeDemo::new();
+        let _ = demo.update(Message::KeepSorted(true));
+        // The demo tree is already in order
+        assert!(!demo.history.can_undo());
+
+        let _ = demo.update(Message::AddRoot);
+        assert_eq!(demo.get_node(&[0]).unwrap().label, "New Root");
+
+        let branch = demo.id_at(&[1, 0]).unwrap();
+        rename(&mut demo, &[1, 0], "Zeta");
+        assert_eq!(demo.path_of(branch), Some(vec![1, 1]));
+
+        // Renaming and moving are undone together
+        let _ = demo.update(Message::Undo);
+        assert_eq!(demo.path_of(branch), Some(vec![1, 0]));
+        assert_eq!(demo.node(branch).unwrap().label, "Branch 1.1");
+    }
+
+    #[test]
+    fn test_checks_propagate_and_survive_add_and_remove() {
+        let mut demo = TreeDemo::new();
+        let root = demo.id_at(&[0]).unwrap();
+        let branch = demo.id_at(&[0, 1]).unwrap();
+        let leaf = demo.id_at(&[0, 0, 0]).unwrap();
+
+        let _ = demo.update(Message::ToggleCheck(root));
+        assert_eq!(demo.checked().len(), 6);
+        assert_eq!(demo.checked()[0], root);
+
+        // An unchecked child makes its ancestors indeterminate
+        let _ = demo.update(Message::AddChild(branch));
+        let child = demo.id_at(&[0, 1, 1]).unwrap();
+        assert_eq!(
+            demo.checks.state(demo.node(root).unwrap()),
+            CheckState::Indeterminate
+        );
+        assert!(!demo.checked().contains(&branch));
+
+        let _ = demo.update(Message::RequestRemove(child));
+        assert_eq!(demo.checks.state(demo.node(root).unwrap()), CheckState::Checked);
+
+        let _ = demo.update(Message::RequestRemove(leaf));
+        let _ = demo.update(Message::Undo);
+        assert_eq!(demo.checked().len(), 6);
+
+        let _ = demo.update(Message::ToggleCheck(leaf));
+        assert_eq!(
+            demo.checked(),
+            [
+                demo.id_at(&[0, 0, 1]).unwrap(),
+                branch,
+                demo.id_at(&[0, 1, 0]).unwrap()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_states_follow_each_update() {
+        let mut demo = TreeDemo::new();
+        let leaf = demo.id_at(&[1, 0]).unwrap();
+        let _ = demo.update(Message::ToggleCheck(leaf));
+        assert!(demo.check_states.is_empty());
+
+        let _ = demo.update(Message::ShowChecks(true));
+        assert_eq!(demo.check_states[&leaf], CheckState::Checked);
+        assert_eq!(demo.checked_count(), demo.checked().len());
+
+        // A checked leaf given a child stays checked
+        let _ = demo.update(Message::AddChild(leaf));
+        let child = demo.id_at(&[1, 0, 0]).unwrap();
+        assert_eq!(demo.check_states[&leaf], CheckState::Checked);
+        assert_eq!(demo.check_states[&child], CheckState::Unchecked);
+        assert_eq!(demo.checked(), [leaf]);
+        assert_eq!(demo.checked_count(), 1);
+    }
+
+    #[test]
+    fn test_selection_survives_structural_changes() {
+        let mut demo = TreeDemo::new();
+        let kept = demo.id_at(&[0, 1]).unwrap();
+        let removed = demo.id_at(&[0, 0]).unwrap();
+
+        let _ = demo.update(Message::Select(kept, SelectMode::Replace));
+        let _ = demo.update(Message::Select(removed, SelectMode::Toggle));
+        let _ = demo.update(Message::AddChild(kept));
+        let _ = demo.update(Message::RemoveChild(removed));
+
+        assert!(demo.selection.is_selected(kept));
+        assert!(!demo.selection.is_selected(removed));
+        assert_eq!(demo.selection.len(), 1);
+    }
+
+    #[test]
+    fn test_navigate_moves_focus_and_selection() {
+        let mut demo = TreeDemo::new();
+
+        let _ = demo.update(Message::Navigate(Navigation::Down));
+        assert_eq!(demo.focused, demo.id_at(&[0]));
+
+        let _ = demo.update(Message::Navigate(Navigation::Down));
+        let branch = demo.id_at(&[0, 0]).unwrap();
+        assert_eq!(demo.focused, Some(branch));
+        assert!(demo.selection.is_selected(branch));
+        assert_eq!(demo.selection.len(), 1);
+
+        let _ = demo.update(Message::Navigate(Navigation::End));
+        assert_eq!(demo.focused, demo.id_at(&[1, 1]));
+    }
+
+    #[test]
+    fn test_page_down_counts_placeholder_rows() {
+        let mut loading = plain("Loading", true, vec![]);
+        loading.load = Load::Loading;
+        let mut roots = vec![plain("First", false, vec![]), loading];
+        roots.extend((2..15).map(|i| plain(&format!("Node {i}"), false, vec![])));
+
+        let mut demo = TreeDemo::new();
+        let _ = demo.update(Message::Opened(Ok((PathBuf::from("nodes.json"), roots))));
+        let first = demo.id_at(&[0]).unwrap();
+        let _ = demo.update(Message::Select(first, SelectMode::Replace));
+
+        // Ten rows fit, and one of them is the placeholder below "Loading"
+        let _ = demo.update(Message::Navigate(Navigation::PageDown));
+        assert_eq!(demo.focused, demo.id_at(&[9]));
+
+        let _ = demo.update(Message::Navigate(Navigation::PageUp));
+        assert_eq!(demo.focused, Some(first));
+    }
+
+    #[test]
+    fn test_navigate_right_and_left_toggle() {
+        let mut demo = TreeDemo::new();
+        let branch = demo.id_at(&[0, 0]).unwrap();
+        let _ = demo.update(Message::Select(branch, SelectMode::Replace));
+
+        let _ = demo.update(Message::Navigate(Navigation::Right));
+        assert!(demo.node(branch).unwrap().open);
+
+        let _ = demo.update(Message::Navigate(Navigation::Right));
+        assert_eq!(demo.focused, demo.id_at(&[0, 0, 0]));
+
+        let _ = demo.update(Message::Navigate(Navigation::Left));
+        assert_eq!(demo.focused, Some(branch));
+
+        let _ = demo.update(Message::Navigate(Navigation::Left));
+        assert!(!demo.node(branch).unwrap().open);
+    }
+
+    #[test]
+    fn test_edit_focused_node() {
+        let mut demo = TreeDemo::new();
+        let branch = demo.id_at(&[1, 0]).unwrap();
+        let _ = demo.update(Message::Select(branch, SelectMode::Replace));
+
+        let _ = demo.update(Message::EditFocused);
+        assert_eq!(demo.editing, Some(branch));
+        assert_eq!(demo.edit_value, "Branch 2.1");
+    }
+
+    #[test]
+    fn test_remove_focused_moves_focus_to_next_row() {
+        let mut demo = TreeDemo::new();
+        let removed = demo.id_at(&[0, 0]).unwrap();
+        let next = demo.id_at(&[0, 1]).unwrap();
+        let _ = demo.update(Message::Select(removed, SelectMode::Replace));
+
+        let _ = demo.update(Message::RemoveFocused);
+        // Branch 1.1 has children, so the removal waits for confirmation
+        assert!(demo.node(removed).is_some());
+        let _ = demo.update(Message::ConfirmRemove);
+
+        assert!(demo.node(removed).is_none());
+        assert_eq!(demo.focused, Some(next));
+    }
+
+    #[test]
+    fn test_remove_focused_last_row_moves_focus_up() {
+        let mut demo = TreeDemo::new();
+        let removed = demo.id_at(&[1, 1]).unwrap();
+        let _ = demo.update(Message::Select(removed, SelectMode::Replace));
+
+        let _ = demo.update(Message::RemoveFocused);
+
+        assert_eq!(demo.focused, demo.id_at(&[1, 0]));
+    }
+
+    #[test]
+    fn test_cancel_edit_keeps_original_label() {
+        let mut demo = TreeDemo::new();
+        let id = demo.id_at(&[0]).unwrap();
+
+        let _ = demo.update(Message::StartEdit(id));
+        let _ = demo.update(Message::EditLabel("Renamed".to_string()));
+        let _ = demo.update(Message::CancelEdit);
+
+        assert_eq!(demo.editing, None);
+        assert_eq!(demo.edit_value, "");
+        assert_eq!(demo.node(id).unwrap().label, "Root 1");
+    }
+
+    #[test]
+    fn test_restart_edit_commits_by_default() {
+        let mut demo = TreeDemo::new();
+        let first = demo.id_at(&[0]).unwrap();
+        let second = demo.id_at(&[1]).unwrap();
+
+        let _ = demo.update(Message::StartEdit(first));
+        let _ = demo.update(Message::EditLabel("Renamed".to_string()));
+        let _ = demo.update(Message::StartEdit(second));
+
+        assert_eq!(demo.node(first).unwrap().label, "Renamed");
+        assert_eq!(demo.editing, Some(second));
+        assert_eq!(demo.edit_value, "Root 2");
+    }
+
+    #[test]
+    fn test_restart_edit_policies() {
+        let mut demo = TreeDemo::new();
+        let first = demo.id_at(&[0]).unwrap();
+        let second = demo.id_at(&[1]).unwrap();
+
+        demo.edit_policy.on_restart = PendingEdit::Cancel;
+        let _ = demo.update(Message::StartEdit(first));
+        let _ = demo.update(Message::EditLabel("Renamed".to_string()));
+        let _ = demo.update(Message::StartEdit(second));
+        assert_eq!(demo.node(first).unwrap().label, "Root 1");
+        assert_eq!(demo.editing, Some(second));
+
+        demo.edit_policy.on_restart = PendingEdit::Keep;
+        let _ = demo.update(Message::EditLabel("Kept".to_string()));
+        let _ = demo.update(Message::StartEdit(first));
+        assert_eq!(demo.editing, Some(second));
+        assert_eq!(demo.edit_value, "Kept");
+    }
+
+    #[test]
+    fn test_start_edit_on_same_node_keeps_value() {
+        let mut demo = TreeDemo::new();
+        let id = demo.id_at(&[0]).unwrap();
+
+        let _ = demo.update(Message::StartEdit(id));
+        let _ = demo.update(Message::EditLabel("Renamed".to_string()));
+        let _ = demo.update(Message::StartEdit(id));
+
+        assert_eq!(demo.edit_value, "Renamed");
+    }
+
+    #[test]
+    fn test_blur_follows_policy() {
+        let mut demo = TreeDemo::new();
+        let id = demo.id_at(&[0]).unwrap();
+
+        let _ = demo.update(Message::StartEdit(id));
+        let _ = demo.update(Message::EditLabel("Renamed".to_string()));
+        let _ = demo.update(Message::EditBlurred);
+        assert_eq!(demo.node(id).unwrap().label, "Renamed");
+        assert_eq!(demo.editing, None);
+
+        demo.edit_policy.on_blur = PendingEdit::Cancel;
+        let _ = demo.update(Message::StartEdit(id));
+        let _ = demo.update(Message::EditLabel("Discarded".to_string()));
+        let _ = demo.update(Message::EditBlurred);
+        assert_eq!(demo.node(id).unwrap().label, "Renamed");
+        assert_eq!(demo.editing, None);
+    }
+
+    #[test]
+    fn test_add_root() {
+        let mut demo = TreeDemo::new();
+
+        let _ = demo.update(Message::AddRoot);
+
+        assert_eq!(demo.roots.len(), 3);
+        assert_eq!(demo.roots[2].label, "New Root");
+        assert_eq!(demo.focused, Some(demo.roots[2].id()));
+    }
+
+    #[test]
+    fn test_remove_root() {
+        let mut demo = TreeDemo::new();
+        let id = demo.id_at(&[1]).unwrap();
+
+        let _ = demo.update(Message::RemoveRoot(id));
+
+        assert_eq!(demo.roots.len(), 1);
+        assert!(demo.node(id).is_none());
+    }
+
+    #[test]
+    fn test_remove_root_ignores_children() {
+        let mut demo = TreeDemo::new();
+        let id = demo.id_at(&[0, 0]).unwrap();
+
+        let _ = demo.update(Message::RemoveRoot(id));
+
+        assert!(demo.node(id).is_some());
+    }
+
+    #[test]
+    fn test_request_remove_leaf_removes_immediately() {
+        let mut demo = TreeDemo::new();
+        let id = demo.id_at(&[1, 0]).unwrap();
+
+        let _ = demo.update(Message::RequestRemove(id));
+
+        assert!(demo.node(id).is_none());
+        assert_eq!(demo.pending_removal, None);
+    }
+
+    #[test]
+    fn test_request_remove_subtree_needs_confirmation() {
+        let mut demo = TreeDemo::new();
+        let id = demo.id_at(&[0]).unwrap();
+
+        let _ = demo.update(Message::RequestRemove(id));
+        assert!(demo.node(id).is_some());
+        assert_eq!(demo.pending_removal, Some(id));
+
+        let _ = demo.update(Message::CancelRemove);
+        assert!(demo.node(id).is_some());
+        assert_eq!(demo.pending_removal, None);
+
+        let _ = demo.update(Message::RequestRemove(id));
+        let _ = demo.update(Message::ConfirmRemove);
+        assert!(demo.node(id).is_none());
+        assert_eq!(demo.roots.len(), 1);
+    }
+
+    #[test]
+    fn test_remove_cancels_edit_inside_subtree() {
+        let mut demo = TreeDemo::new();
+        let branch = demo.id_at(&[0, 0]).unwrap();
+        let leaf = demo.id_at(&[0, 0, 0]).unwrap();
+
+        let _ = demo.update(Message::StartEdit(leaf));
+        let _ = demo.update(Message::RemoveChild(branch));
+
+        assert_eq!(demo.editing, None);
+        assert_eq!(demo.edit_value, "");
+    }
+
+    #[test]
+    fn test_undo_redo_toggle() {
+        let mut demo = TreeDemo::new();
+        let id = demo.id_at(&[0, 0]).unwrap();
+
+        let _ = demo.update(Message::Toggle(id));
+        assert!(demo.node(id).unwrap().open);
+
+        let _ = demo.update(Message::Undo);
+        assert!(!demo.node(id).unwrap().open);
+
+        let _ = demo.update(Message::Redo);
+        assert!(demo.node(id).unwrap().open);
+    }
+
+    #[test]
+    fn test_undo_rename() {
+        let mut demo = TreeDemo::new();
+        let id = demo.id_at(&[0]).unwrap();
+
+        let _ = demo.update(Message::StartEdit(id));
+        let _ = demo.update(Message::EditLabel("Renamed".to_string()));
+        let _ = demo.update(Message::FinishEdit);
+        let _ = demo.update(Message::Undo);
+
+        assert_eq!(demo.node(id).unwrap().label, "Root 1");
+    }
+
+    #[test]
+    fn test_unchanged_rename_not_recorded() {
+        let mut demo = TreeDemo::new();
+        let id = demo.id_at(&[0]).unwrap();
+
+        let _ = demo.update(Message::StartEdit(id));
+        let _ = demo.update(Message::FinishEdit);
+
+        assert!(!demo.history.can_undo());
+    }
+
+    #[test]
+    fn test_undo_add_child_is_one_step() {
+        let mut demo = TreeDemo::new();
+        let id = demo.id_at(&[1, 0]).unwrap();
+
+        let _ = demo.update(Message::AddChild(id));
+        let _ = demo.update(Message::Undo);
+
+        let node = demo.node(id).unwrap();
+        assert!(node.children.is_empty());
+        assert!(!node.open);
+        assert!(!demo.history.can_undo());
+    }
+
+    #[test]
+    fn test_undo_remove_restores_subtree() {
+        let mut demo = TreeDemo::new();
+        let branch = demo.id_at(&[0, 0]).unwrap();
+        let leaf = demo.id_at(&[0, 0, 1]).unwrap();
+
+        let _ = demo.update(Message::RemoveChild(branch));
+        assert!(demo.node(leaf).is_none());
+
+        let _ = demo.update(Message::Undo);
+        assert_eq!(demo.path_of(branch), Some(vec![0, 0]));
+        assert_eq!(demo.path_of(leaf), Some(vec![0, 0, 1]));
+    }
+
+    #[test]
+    fn test_undo_add_root_moves_focus() {
+        let mut demo = TreeDemo::new();
+
+        let _ = demo.update(Message::AddRoot);
+        let _ = demo.update(Message::Undo);
+
+        assert_eq!(demo.roots.len(), 2);
+        assert_eq!(demo.focused, demo.id_at(&[1, 1]));
+    }
+
+    #[test]
+    fn test_move_node_to_other_parent() {
+        let mut demo = TreeDemo::new();
+        let branch = demo.id_at(&[0, 0]).unwrap();
+        let target = demo.id_at(&[1, 1]).unwrap();
+
+        let _ = demo.update(Message::MoveNode {
+            from: branch,
+            to_parent: Some(target),
+            index: 0,
+        });
+
+        assert_eq!(demo.path_of(branch), Some(vec![1, 1, 0]));
+        assert_eq!(demo.node(branch).unwrap().children.len(), 2);
+        assert!(demo.node(target).unwrap().open);
+    }
+
+    #[test]
+    fn test_move_node_refuses_descendant() {
+        let mut demo = TreeDemo::new();
+        let root = demo.id_at(&[0]).unwrap();
+        let leaf = demo.id_at(&[0, 0, 0]).unwrap();
+
+        let _ = demo.update(Message::MoveNode {
+            from: root,
+            to_parent: Some(leaf),
+            index: 0,
+        });
+
+        assert_eq!(demo.path_of(root), Some(vec![0]));
+        assert!(!demo.history.can_undo());
+    }
+
+    #[test]
+    fn test_move_node_onto_itself_not_recorded() {
+        let mut demo = TreeDemo::new();
+        let branch = demo.id_at(&[0, 1]).unwrap();
+        let parent = demo.id_at(&[0]).unwrap();
+
+        let _ = demo.update(Message::MoveNode {
+            from: branch,
+            to_parent: Some(parent),
+            index: usize::MAX,
+        });
+
+        assert!(!demo.history.can_undo());
+    }
+
+    #[test]
+    fn test_drop_before_and_inside() {
+        let mut demo = TreeDemo::new();
+        let moved = demo.id_at(&[1, 1]).unwrap();
+        let target = demo.id_at(&[0]).unwrap();
+
+        let _ = demo.update(Message::Drop(moved, target, DropPosition::Before));
+        assert_eq!(demo.path_of(moved), Some(vec![0]));
+
+        let leaf = demo.id_at(&[2, 0]).unwrap();
+        let _ = demo.update(Message::Drop(leaf, moved, DropPosition::Inside));
+        assert_eq!(demo.path_of(leaf), Some(vec![0, 0]));
+        assert!(demo.node(moved).unwrap().open);
+    }
+
+    #[test]
+    fn test_undo_move_is_one_step() {
+        let mut demo = TreeDemo::new();
+        let leaf = demo.id_at(&[1, 0]).unwrap();
+        let target = demo.id_at(&[0, 1]).unwrap();
+
+        let _ = demo.update(Message::Drop(leaf, target, DropPosition::Inside));
+        let _ = demo.update(Message::Undo);
+
+        assert_eq!(demo.path_of(leaf), Some(vec![1, 0]));
+        assert!(!demo.node(target).unwrap().open);
+        assert!(!demo.history.can_undo());
+    }
+
+    #[test]
+    fn test_copy_and_paste_as_sibling() {
+        let mut demo = TreeDemo::new();
+        let branch = demo.id_at(&[0, 0]).unwrap();
+        let _ = demo.update(Message::Select(branch, SelectMode::Replace));
+
+        let _ = demo.update(Message::Copy);
+        assert_eq!(
+            demo.clipboard.text(),
+            "Branch 1.1\n  Leaf 1.1.1\n  Leaf 1.1.2\n"
+        );
+
+        let text = Contents::text(demo.clipboard.text());
+        let _ = demo.update(Message::Pasted(Placement::Sibling, Some(text)));
+
+        let copy = demo.get_node(&[0, 1]).unwrap();
+        assert_eq!(copy.label, "Branch 1.1");
+        assert_eq!(copy.children.len(), 2);
+        assert_ne!(copy.id(), branch);
+        assert_eq!(demo.focused, Some(copy.id()));
+        assert_eq!(demo.get_node(&[0, 2]).unwrap().label, "Branch 1.2");
+    }
+
+    #[test]
+    fn test_paste_foreign_text_as_child() {
+        let mut demo = TreeDemo::new();
+        let target = demo.id_at(&[1, 0]).unwrap();
+        let _ = demo.update(Message::Select(target, SelectMode::Replace));
+
+        let _ = demo.update(Message::Pasted(
+            Placement::Child,
+            Some(Contents::text("Pasted\n  Nested\nSecond")),
+        ));
+
+        let node = demo.node(target).unwrap();
+        assert!(node.open);
+        assert_eq!(node.children.len(), 2);
+        assert_eq!(node.children[0].children[0].label, "Nested");
+        assert_eq!(demo.selection.len(), 2);
+    }
+
+    #[test]
+    fn test_cut_removes_and_paste_restores_elsewhere() {
+        let mut demo = TreeDemo::new();
+        let branch = demo.id_at(&[0, 1]).unwrap();
+        let _ = demo.update(Message::Select(branch, SelectMode::Replace));
+
+        let _ = demo.update(Message::Cut);
+        assert!(demo.node(branch).is_none());
+
+        let target = demo.id_at(&[1]).unwrap();
+        let _ = demo.update(Message::Select(target, SelectMode::Replace));
+        let text = Contents::text(demo.clipboard.text());
+        let _ = demo.update(Message::Pasted(Placement::Child, Some(text)));
+
+        let root = demo.node(target).unwrap();
+        assert_eq!(root.children[2].label, "Branch 1.2");
+        assert_eq!(root.children[2].children[0].label, "Leaf 1.2.1");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_copy_node_with_non_unicode_path() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = PathBuf::from(OsStr::from_bytes(b"/tmp/caf\xe9"));
+        let entry = filesystem::Entry::new(path, Kind::File);
+        let mut demo = TreeDemo::new();
+        let _ = demo.update(Message::FolderOpened(Ok(Node::with_data(
+            "caf\u{fffd}",
+            Some(entry),
+            false,
+            vec![],
+        ))));
+        let _ = demo.update(Message::Select(demo.id_at(&[0]).unwrap(), SelectMode::Replace));
+
+        let contents = demo.copy_targets().unwrap();
+        assert!(contents.html.is_some());
+        assert_eq!(demo.error, None);
+    }
+
+    #[test]
+    fn test_paste_into_another_instance_keeps_open_state() {
+        let mut source = TreeDemo::new();
+        let branch = source.id_at(&[0, 0]).unwrap();
+        let _ = source.update(Message::Toggle(branch));
+        let _ = source.update(Message::Select(branch, SelectMode::Replace));
+        let contents = source.copy_targets().unwrap();
+
+        let mut demo = TreeDemo::new();
+        let _ = demo.update(Message::Pasted(Placement::Sibling, Some(contents)));
+
+        assert_eq!(demo.roots[2].label, "Branch 1.1");
+        assert!(demo.roots[2].open);
+        assert_eq!(demo.roots[2].children.len(), 2);
+    }
+
+    #[test]
+    fn test_paste_under_missing_focus_reports_error() {
+        let mut demo = TreeDemo::new();
+        let leaf = demo.id_at(&[0, 1, 0]).unwrap();
+        let _ = demo.update(Message::Select(leaf, SelectMode::Replace));
+        demo.roots[0].children[1].children.clear();
+
+        let text = Contents::text("Pasted");
+        let _ = demo.update(Message::Pasted(Placement::Child, Some(text)));
+
+        assert!(demo.error.is_some());
+        assert!(!demo.history.can_undo());
+        assert_eq!(demo.selection.iter().collect::<Vec<_>>(), [leaf]);
+    }
+
+    #[test]
+    fn test_paste_with_empty_clipboard_does_nothing() {
+        let mut demo = TreeDemo::new();
+
+        let _ = demo.update(Message::Pasted(Placement::Sibling, None));
+
+        assert!(!demo.history.can_undo());
+    }
+
+    #[test]
+    fn test_duplicate_selected_nodes() {
+        let mut demo = TreeDemo::new();
+        let first = demo.id_at(&[1, 0]).unwrap();
+        let second = demo.id_at(&[1, 1]).unwrap();
+        let _ = demo.update(Message::Select(first, SelectMode::Replace));
+        let _ = demo.update(Message::Select(second, SelectMode::Toggle));
+
+        let _ = demo.update(Message::Duplicate);
+
+        let labels: Vec<_> = demo.roots[1]
+            .children
+            .iter()
+            .map(|node| node.label.as_str())
+            .collect();
+        assert_eq!(labels, ["Branch 2.1", "Branch 2.1", "Branch 2.2", "Branch 2.2"]);
+        assert_eq!(demo.selection.len(), 2);
+
+        let _ = demo.update(Message::Undo);
+        assert_eq!(demo.roots[1].children.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_tree_round_trip() {
+        let demo = TreeDemo::new();
+        let json = serde_json::to_string_pretty(&demo.roots).unwrap();
+
+        let roots = parse_tree(&json, Format::Json).unwrap();
+
+        assert_eq!(roots.len(), 2);
+        assert_eq!(roots[0].children[0].children[1].label, "Leaf 1.1.2");
+        assert!(roots[0].open);
+    }
+
+    #[test]
+    fn test_parse_tree_reports_malformed_file() {
+        let error = parse_tree(r#"[{"label": "Root", "open": "yes"}]"#, Format::Json).unwrap_err();
+
+        let message = error.to_string();
+        assert!(message.starts_with("Malformed tree file: "), "{message}");
+        assert!(message.contains("line 1"), "{message}");
+    }
+
+    #[test]
+    fn test_opened_replaces_tree_and_resets_state() {
+        let mut demo = TreeDemo::new();
+        let id = demo.id_at(&[0]).unwrap();
+        let _ = demo.update(Message::Select(id, SelectMode::Replace));
+        let _ = demo.update(Message::Toggle(id));
+        assert!(demo.is_dirty());
+
+        let roots = parse_tree(r#"[{"label": "Loaded"}]"#, Format::Json).unwrap();
+        let _ = demo.update(Message::Opened(Ok((PathBuf::from("tree.json"), roots))));
+
+        assert_eq!(demo.roots[0].label, "Loaded");
+        assert!(!demo.is_dirty());
+        assert!(!demo.history.can_undo());
+        assert!(demo.selection.is_empty());
+        assert_eq!(demo.focused, None);
+        assert_eq!(demo.title(), "tree.json - Tree Demo");
+    }
+
+    #[test]
+    fn test_dirty_tracking_follows_saves_and_undo() {
+        let mut demo = TreeDemo::new();
+        assert!(!demo.is_dirty());
+
+        let _ = demo.update(Message::AddRoot);
+        assert!(demo.is_dirty());
+        assert_eq!(demo.title(), "Untitled* - Tree Demo");
+
+        let revision = demo.history.revision();
+        let _ = demo.update(Message::Saved(revision, Ok(PathBuf::from("saved.json"))));
+        assert!(!demo.is_dirty());
+
+        let _ = demo.update(Message::Undo);
+        assert!(demo.is_dirty());
+        let _ = demo.update(Message::Redo);
+        assert!(!demo.is_dirty());
+    }
+
+    #[test]
+    fn test_failed_save_keeps_dirty_and_shows_error() {
+        let mut demo = TreeDemo::new();
+        let _ = demo.update(Message::AddRoot);
+
+        let revision = demo.history.revision();
+        let _ = demo.update(Message::Saved(
+            revision,
+            Err(Error::Io(io::ErrorKind::PermissionDenied)),
+        ));
+
+        assert!(demo.is_dirty());
+        assert_eq!(
+            demo.error.as_deref(),
+            Some("Could not access the file: permission denied")
+        );
+
+        let _ = demo.update(Message::Saved(revision, Err(Error::DialogClosed)));
+        assert!(demo.is_dirty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_save_tree_with_non_unicode_path() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = PathBuf::from(OsStr::from_bytes(b"/tmp/caf\xe9"));
+        let mut demo = TreeDemo::new();
+        let entry = filesystem::Entry::new(path, Kind::Folder);
+        let root = Node::with_data("caf\u{fffd}", Some(entry), false, vec![]);
+        let _ = demo.update(Message::FolderOpened(Ok(root)));
+
+        let contents = serialize_tree(&demo.roots, Path::new("tree.json")).unwrap();
+        let roots = parse_tree(&contents, Format::Json).unwrap();
+        let entry = roots[0].data.as_ref().unwrap();
+        assert_eq!(entry.path, Path::new("/tmp/caf\u{fffd}"));
+    }
+
+    #[test]
+    fn test_format_follows_extension() {
+        assert_eq!(Format::of(Path::new("tree.json")), Format::Json);
+        assert_eq!(Format::of(Path::new("notes.OPML")), Format::Opml);
+        assert_eq!(Format::of(Path::new("tree")), Format::Json);
+        assert_eq!(Format::of(Path::new("notes.txt")), Format::Text);
+        assert_eq!(Format::of(Path::new("README.md")), Format::Markdown);
+    }
+
+    #[test]
+    fn test_opml_file_round_trip() {
+        let demo = TreeDemo::new();
+
+        let contents = serialize_tree(&demo.roots, Path::new("outline.opml")).unwrap();
+        assert!(contents.contains("<title>outline</title>"));
+
+        let roots = parse_tree(&contents, Format::Opml).unwrap();
+        assert_eq!(roots.len(), 2);
+        assert_eq!(roots[1].children[1].label, "Branch 2.2");
+        assert!(roots[1].open);
+        assert!(!roots[0].children[0].open);
+    }
+
+    #[test]
+    fn test_malformed_opml_reports_error() {
+        let error = parse_tree("<opml><body><outline></body>", Format::Opml).unwrap_err();
+
+        assert!(error.to_string().starts_with("Malformed tree file: "));
+    }
+
+    #[test]
+    fn test_markdown_file_round_trip() {
+        let demo = TreeDemo::new();
+
+        let contents = serialize_tree(&demo.roots, Path::new("notes.md")).unwrap();
+        assert!(contents.starts_with("- Root 1\n  - Branch 1.1\n"), "{contents}");
+
+        let roots = parse_tree(&contents, Format::Markdown).unwrap();
+        assert_eq!(roots.len(), 2);
+        assert_eq!(roots[1].children[1].label, "Branch 2.2");
+    }
+
+    #[test]
+    fn test_inconsistent_outline_reports_line() {
+        let error = parse_tree("Root\n  Child\n     Grandchild", Format::Text).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Malformed tree file: line 3: indentation of 5 spaces is not a multiple of 2"
+        );
+    }
+
+    #[test]
+    fn test_paste_inconsistent_outline_shows_error() {
+        let mut demo = TreeDemo::new();
+        let before = demo.roots.len();
+
+        let _ = demo.update(Message::Pasted(
+            Placement::Sibling,
+            Some(Contents::text("One\n\t  Two")),
+        ));
+
+        assert_eq!(demo.roots.len(), before);
+        assert_eq!(
+            demo.error.as_deref(),
+            Some("Could not paste: line 2: indentation mixes tabs and spaces")
+        );
+    }
+
+    #[test]
+    fn test_folder_loads_children_on_toggle() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        touch(dir, "src/bin/tool.rs");
+        touch(dir, "Cargo.toml");
+
+        let mut demo = TreeDemo::new();
+        let _ = demo.update(Message::Undo);
+        let _ = demo.update(Message::FolderOpened(Ok(filesystem::open(dir).unwrap())));
+
+        assert_eq!(demo.roots.len(), 1);
+        assert!(!demo.history.can_undo());
+        assert_eq!(demo.get_node(&[0, 0]).unwrap().label, "src");
+        assert_eq!(demo.get_node(&[0, 1]).unwrap().label, "Cargo.toml");
+        // "src" gets an arrow, but its contents are not read yet
+        let src = demo.id_at(&[0, 0]).unwrap();
+        assert_eq!(demo.node(src).unwrap().load, Load::Unloaded);
+        assert!(demo.node(src).unwrap().is_expandable());
+        assert!(demo.watches.contains(dir));
+        assert!(!demo.watches.contains(&dir.join("src")));
+
+        let _ = demo.update(Message::Toggle(src));
+        assert!(demo.node(src).unwrap().open);
+        assert_eq!(demo.node(src).unwrap().load, Load::Loading);
+
+        let _ = demo.update(Message::Loaded(src, Err("permission denied".into())));
+        assert_eq!(
+            demo.node(src).unwrap().load,
+            Load::Failed("permission denied".into())
+        );
+
+        let _ = demo.update(Message::Retry(src));
+        assert_eq!(demo.node(src).unwrap().load, Load::Loading);
+        let children = filesystem::read_dir(&dir.join("src")).unwrap();
+        let _ = demo.update(Message::Loaded(src, Ok(children)));
+        assert!(demo.node(src).unwrap().load.is_loaded());
+        assert_eq!(demo.get_node(&[0, 0, 0]).unwrap().label, "bin");
+        assert!(demo.watches.contains(&dir.join("src")));
+
+        // Only the expansion is recorded, not the read
+        let _ = demo.update(Message::Undo);
+        assert!(!demo.node(src).unwrap().open);
+        assert!(demo.node(src).unwrap().load.is_loaded());
+        assert!(!demo.history.can_undo());
+    }
+
+    #[test]
+    fn test_undo_removal_restarts_interrupted_load() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        touch(dir, "src/main.rs");
+
+        let mut demo = TreeDemo::new();
+        let _ = demo.update(Message::FolderOpened(Ok(filesystem::open(dir).unwrap())));
+        let src = demo.id_at(&[0, 0]).unwrap();
+        let _ = demo.update(Message::Toggle(src));
+        let _ = demo.update(Message::RemoveChild(src));
+
+        // The read finishes while the folder is detached and is dropped
+        let children = filesystem::read_dir(&dir.join("src")).unwrap();
+        let _ = demo.update(Message::Loaded(src, Ok(children)));
+
+        let _ = demo.update(Message::Undo);
+        assert!(demo.node(src).unwrap().open);
+        assert_eq!(demo.node(src).unwrap().load, Load::Loading);
+
+        let children = filesystem::read_dir(&dir.join("src")).unwrap();
+        let _ = demo.update(Message::Loaded(src, Ok(children)));
+        assert!(demo.node(src).unwrap().load.is_loaded());
+        assert_eq!(demo.get_node(&[0, 0, 0]).unwrap().label, "main.rs");
+    }
+
+    #[test]
+    fn test_opening_a_folder_for_new_children_loads_it() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        touch(dir, "docs/guide.md");
+        touch(dir, "src/main.rs");
+        touch(dir, "notes.txt");
+
+        let mut demo = TreeDemo::new();
+        let _ = demo.update(Message::FolderOpened(Ok(filesystem::open(dir).unwrap())));
+        let docs = demo.id_at(&[0, 0]).unwrap();
+        let src = demo.id_at(&[0, 1]).unwrap();
+        let notes = demo.id_at(&[0, 2]).unwrap();
+
+        let _ = demo.update(Message::AddChild(docs));
+        assert!(demo.node(docs).unwrap().open);
+        assert_eq!(demo.node(docs).unwrap().load, Load::Loading);
+        let children = filesystem::read_dir(&dir.join("docs")).unwrap();
+        let _ = demo.update(Message::Loaded(docs, Ok(children)));
+        let children = &demo.node(docs).unwrap().children;
+        assert_eq!(children[0].label, "guide.md");
+        assert_eq!(children[1].label, "New Node");
+
+        let _ = demo.update(Message::MoveNode {
+            from: notes,
+            to_parent: Some(src),
+            index: 0,
+        });
+        assert!(demo.node(src).unwrap().open);
+        assert_eq!(demo.node(src).unwrap().load, Load::Loading);
+    }
+
+    #[test]
+    fn test_disk_changes_keep_selection_and_expansion() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        touch(dir, "notes/todo.md");
+        touch(dir, "old.txt");
+
+        let mut demo = TreeDemo::new();
+        let _ = demo.update(Message::FolderOpened(Ok(filesystem::open(dir).unwrap())));
+        let notes = demo.id_at(&[0, 0]).unwrap();
+        let old = demo.id_at(&[0, 1]).unwrap();
+        let _ = demo.update(Message::Toggle(notes));
+        let children = filesystem::read_dir(&dir.join("notes")).unwrap();
+        let _ = demo.update(Message::Loaded(notes, Ok(children)));
+        let _ = demo.update(Message::Select(old, SelectMode::Replace));
+        assert_eq!(watch::folders(&demo.roots).len(), 2);
+
+        std::fs::rename(dir.join("old.txt"), dir.join("new.txt")).unwrap();
+        let _ = demo.update(Message::Changed(watch::Change::Renamed {
+            from: dir.join("old.txt"),
+            to: dir.join("new.txt"),
+            kind: Kind::File,
+        }));
+        let done = touch(dir, "notes/done.md");
+        let _ = demo.update(Message::Changed(watch::Change::Created(done, Kind::File)));
+
+        assert_eq!(demo.node(old).unwrap().label, "new.txt");
+        assert!(demo.selection.is_selected(old));
+        assert!(demo.node(notes).unwrap().open);
+        assert_eq!(demo.get_node(&[0, 0, 0]).unwrap().label, "done.md");
+
+        let _ = demo.update(Message::Changed(watch::Change::Removed(dir.join("new.txt"))));
+        assert!(demo.node(old).is_none());
+        assert!(demo.selection.is_empty());
+    }
+}
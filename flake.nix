@@ -48,6 +48,12 @@
             lockFile = ./Cargo.lock;
           };
 
+          # The demo is an example of the library, which cargo does not install
+          cargoBuildFlags = [ "--example" "iced-demo" ];
+          postInstall = ''
+            install -Dm755 target/*/release/examples/iced-demo $out/bin/iced-demo
+          '';
+
           nativeBuildInputs = [ pkgs.pkg-config ];
           buildInputs = with pkgs; [ 
             libGL 
@@ -0,0 +1,590 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::tree::{Load, Node, NodeId};
+
+/// A single node stored in a [`Tree`], linked to its relatives by slot index
+#[derive(Debug, Clone)]
+pub struct Entry<T = ()> {
+    id: NodeId,
+    pub label: String,
+    pub open: bool,
+    pub load: Load,
+    pub data: T,
+    parent: Option<usize>,
+    first_child: Option<usize>,
+    last_child: Option<usize>,
+    prev_sibling: Option<usize>,
+    next_sibling: Option<usize>,
+}
+
+impl<T> Entry<T> {
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+}
+
+/// Arena-backed forest with constant-time lookup by [`NodeId`]
+///
+/// Nodes live in a flat slab and refer to each other through parent,
+/// first-child and sibling links instead of owning their children, so
+/// finding a node or its parent never walks the tree. Every id is stored at
+/// most once: adding a node whose id, or one of whose descendants' ids, is
+/// already in the arena is refused.
+///
+/// [`TreeView`](crate::TreeView), [`Filter`](crate::Filter),
+/// [`Search`](crate::Search) and the other modules work on `&[Node<T>]`
+/// only; to show or search a `Tree`, convert it with [`Tree::to_nodes`].
+#[derive(Debug, Clone)]
+pub struct Tree<T = ()> {
+    slots: Vec<Option<Entry<T>>>,
+    free: Vec<usize>,
+    index: HashMap<NodeId, usize>,
+    first_root: Option<usize>,
+    last_root: Option<usize>,
+}
+
+impl<T> Default for Tree<T> {
+    fn default() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+            index: HashMap::new(),
+            first_root: None,
+            last_root: None,
+        }
+    }
+}
+
+impl<T: Clone> Tree<T> {
+    /// Converts the arena back into an owned forest
+    pub fn to_nodes(&self) -> Vec<Node<T>> {
+        self.sibling_slots(self.first_root)
+            .map(|slot| self.build(slot))
+            .collect()
+    }
+
+    /// Reconstructs the owned subtree rooted at a slot
+    fn build(&self, slot: usize) -> Node<T> {
+        // Each frame holds a slot, its next child to build and the children
+        // built so far, so deep trees do not exhaust the call stack
+        let mut stack = vec![(slot, self.entry(slot).first_child, Vec::new())];
+        loop {
+            let (_, next, _) = stack.last_mut().expect("stack holds the root");
+            if let Some(child) = *next {
+                *next = self.entry(child).next_sibling;
+                stack.push((child, self.entry(child).first_child, Vec::new()));
+                continue;
+            }
+
+            let (slot, _, children) = stack.pop().expect("stack holds the root");
+            let entry = self.entry(slot);
+            let mut node = Node::with_id(
+                entry.id,
+                &entry.label,
+                entry.data.clone(),
+                entry.open,
+                children,
+            );
+            node.load = entry.load.clone();
+            match stack.last_mut() {
+                Some((_, _, siblings)) => siblings.push(node),
+                None => return node,
+            }
+        }
+    }
+}
+
+impl<T> Tree<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds an arena from a forest of nodes, keeping their ids. A root
+    /// holding an id seen earlier in the forest is left out.
+    pub fn from_nodes(roots: Vec<Node<T>>) -> Self {
+        let mut tree = Self::new();
+        for root in roots {
+            tree.add_root(root);
+        }
+        tree
+    }
+
+    /// Number of nodes in the arena
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.index.contains_key(&id)
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<&Entry<T>> {
+        self.index.get(&id).map(|&slot| self.entry(slot))
+    }
+
+    pub fn get_mut(&mut self, id: NodeId) -> Option<&mut Entry<T>> {
+        let slot = *self.index.get(&id)?;
+        self.slots[slot].as_mut()
+    }
+
+    /// Returns the parent of a node, or None for roots and unknown ids
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        let parent = self.get(id)?.parent?;
+        Some(self.entry(parent).id)
+    }
+
+    /// Iterates over the root nodes in order
+    pub fn roots(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.sibling_slots(self.first_root)
+            .map(|slot| self.entry(slot).id)
+    }
+
+    /// Iterates over the direct children of a node in order
+    pub fn children(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        let first = self.get(id).and_then(|entry| entry.first_child);
+        self.sibling_slots(first).map(|slot| self.entry(slot).id)
+    }
+
+    /// Appends a node and its descendants as a new root. Returns None, and
+    /// leaves the arena untouched, if one of their ids is already taken.
+    pub fn add_root(&mut self, root: Node<T>) -> Option<NodeId> {
+        if !self.accepts(&root) {
+            return None;
+        }
+        let id = root.id();
+        let slot = self.alloc(root, None);
+        self.link_last(None, slot);
+        Some(id)
+    }
+
+    /// Adds a child node to the end of the parent's children list.
+    /// Returns false if the parent does not exist or one of the ids of the
+    /// child's subtree is already taken.
+    pub fn add_child(&mut self, parent: NodeId, child: Node<T>) -> bool {
+        let Some(&parent_slot) = self.index.get(&parent) else {
+            return false;
+        };
+        if !self.accepts(&child) {
+            return false;
+        }
+        let slot = self.alloc(child, Some(parent_slot));
+        self.link_last(Some(parent_slot), slot);
+        true
+    }
+
+    /// Inserts a child at the specified index.
+    /// If index is greater than the length, the child is added at the end.
+    /// Returns false if the parent does not exist or one of the ids of the
+    /// child's subtree is already taken.
+    pub fn insert_child(&mut self, parent: NodeId, index: usize, child: Node<T>) -> bool {
+        let Some(&parent_slot) = self.index.get(&parent) else {
+            return false;
+        };
+        if !self.accepts(&child) {
+            return false;
+        }
+        let first = self.entry(parent_slot).first_child;
+        let Some(next) = self.sibling_slots(first).nth(index) else {
+            return self.add_child(parent, child);
+        };
+
+        let slot = self.alloc(child, Some(parent_slot));
+        let prev = self.entry(next).prev_sibling;
+        {
+            let entry = self.entry_mut(slot);
+            entry.prev_sibling = prev;
+            entry.next_sibling = Some(next);
+        }
+        self.entry_mut(next).prev_sibling = Some(slot);
+        match prev {
+            Some(prev) => self.entry_mut(prev).next_sibling = Some(slot),
+            None => self.entry_mut(parent_slot).first_child = Some(slot),
+        }
+        true
+    }
+
+    /// Removes the child at the specified index together with its descendants.
+    /// Returns the removed subtree if successful, None if the parent does not
+    /// exist or the index is out of bounds.
+    pub fn remove_child(&mut self, parent: NodeId, index: usize) -> Option<Node<T>> {
+        let parent_slot = *self.index.get(&parent)?;
+        let first = self.entry(parent_slot).first_child;
+        let slot = self.sibling_slots(first).nth(index)?;
+        Some(self.detach(slot))
+    }
+
+    /// Removes a node anywhere in the arena, including roots, with its descendants
+    pub fn remove(&mut self, id: NodeId) -> Option<Node<T>> {
+        let slot = *self.index.get(&id)?;
+        Some(self.detach(slot))
+    }
+
+    fn entry(&self, slot: usize) -> &Entry<T> {
+        self.slots[slot].as_ref().expect("linked slot is occupied")
+    }
+
+    fn entry_mut(&mut self, slot: usize) -> &mut Entry<T> {
+        self.slots[slot].as_mut().expect("linked slot is occupied")
+    }
+
+    fn sibling_slots(&self, first: Option<usize>) -> impl Iterator<Item = usize> + '_ {
+        std::iter::successors(first, |&slot| self.entry(slot).next_sibling)
+    }
+
+    /// Whether a subtree can be stored: none of its ids may be in the arena
+    /// already or appear twice within it
+    fn accepts(&self, node: &Node<T>) -> bool {
+        let mut seen = HashSet::new();
+        let mut stack = vec![node];
+        while let Some(node) = stack.pop() {
+            if self.index.contains_key(&node.id()) || !seen.insert(node.id()) {
+                return false;
+            }
+            stack.extend(&node.children);
+        }
+        true
+    }
+
+    /// Stores a node and its descendants; returns the node's slot
+    fn alloc(&mut self, node: Node<T>, parent: Option<usize>) -> usize {
+        let (slot, children) = self.alloc_entry(node, parent);
+        let mut stack = vec![(slot, children.into_iter())];
+        while let Some((parent, children)) = stack.last_mut() {
+            let parent = *parent;
+            match children.next() {
+                Some(child) => {
+                    let (child_slot, grandchildren) = self.alloc_entry(child, Some(parent));
+                    self.link_last(Some(parent), child_slot);
+                    stack.push((child_slot, grandchildren.into_iter()));
+                }
+                None => {
+                    stack.pop();
+                }
+            }
+        }
+        slot
+    }
+
+    /// Stores a single node without linking it; returns its slot and the
+    /// children still to be stored
+    fn alloc_entry(&mut self, node: Node<T>, parent: Option<usize>) -> (usize, Vec<Node<T>>) {
+        let id = node.id();
+        let Node {
+            label,
+            open,
+            children,
+            load,
+            data,
+            ..
+        } = node;
+
+        let entry = Entry {
+            id,
+            label,
+            open,
+            load,
+            data,
+            parent,
+            first_child: None,
+            last_child: None,
+            prev_sibling: None,
+            next_sibling: None,
+        };
+        let slot = match self.free.pop() {
+            Some(slot) => {
+                self.slots[slot] = Some(entry);
+                slot
+            }
+            None => {
+                self.slots.push(Some(entry));
+                self.slots.len() - 1
+            }
+        };
+        self.index.insert(id, slot);
+        (slot, children)
+    }
+
+    /// Links an allocated slot as the last child of `parent` (or last root)
+    fn link_last(&mut self, parent: Option<usize>, slot: usize) {
+        let last = match parent {
+            Some(parent) => self.entry(parent).last_child,
+            None => self.last_root,
+        };
+        self.entry_mut(slot).prev_sibling = last;
+        match last {
+            Some(last) => self.entry_mut(last).next_sibling = Some(slot),
+            None => match parent {
+                Some(parent) => self.entry_mut(parent).first_child = Some(slot),
+                None => self.first_root = Some(slot),
+            },
+        }
+        match parent {
+            Some(parent) => self.entry_mut(parent).last_child = Some(slot),
+            None => self.last_root = Some(slot),
+        }
+    }
+
+    /// Unlinks a slot from its siblings and frees it with its descendants
+    fn detach(&mut self, slot: usize) -> Node<T> {
+        let (parent, prev, next) = {
+            let entry = self.entry(slot);
+            (entry.parent, entry.prev_sibling, entry.next_sibling)
+        };
+        match prev {
+            Some(prev) => self.entry_mut(prev).next_sibling = next,
+            None => match parent {
+                Some(parent) => self.entry_mut(parent).first_child = next,
+                None => self.first_root = next,
+            },
+        }
+        match next {
+            Some(next) => self.entry_mut(next).prev_sibling = prev,
+            None => match parent {
+                Some(parent) => self.entry_mut(parent).last_child = prev,
+                None => self.last_root = prev,
+            },
+        }
+
+        self.take(slot)
+    }
+
+    /// Moves the subtree rooted at a slot out of the arena, freeing its slots
+    fn take(&mut self, slot: usize) -> Node<T> {
+        // Frames as in `build`; a slot is freed once its children are taken
+        let mut stack = vec![(slot, self.entry(slot).first_child, Vec::new())];
+        loop {
+            let (_, next, _) = stack.last_mut().expect("stack holds the root");
+            if let Some(child) = *next {
+                *next = self.entry(child).next_sibling;
+                stack.push((child, self.entry(child).first_child, Vec::new()));
+                continue;
+            }
+
+            let (slot, _, children) = stack.pop().expect("stack holds the root");
+            let entry = self.slots[slot].take().expect("taken slot is occupied");
+            self.index.remove(&entry.id);
+            self.free.push(slot);
+            let mut node = Node::with_id(entry.id, &entry.label, entry.data, entry.open, children);
+            node.load = entry.load;
+            match stack.last_mut() {
+                Some((_, _, siblings)) => siblings.push(node),
+                None => return node,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The crate's sample forest in an arena, with the id of its first root
+    fn sample() -> (Tree, NodeId) {
+        let roots = crate::tree::tests::sample();
+        let root = roots[0].id();
+        (Tree::from_nodes(roots), root)
+    }
+
+    fn labels(tree: &Tree, parent: NodeId) -> Vec<String> {
+        tree.children(parent)
+            .map(|id| tree.get(id).unwrap().label.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_from_nodes_preserves_structure_and_ids() {
+        let leaf = Node::new("Leaf", false, vec![]);
+        let leaf_id = leaf.id();
+        let root = Node::new("Root", true, vec![leaf]);
+        let root_id = root.id();
+
+        let tree = Tree::from_nodes(vec![root]);
+
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree.roots().collect::<Vec<_>>(), vec![root_id]);
+        assert_eq!(tree.get(leaf_id).unwrap().label, "Leaf");
+        assert_eq!(tree.parent(leaf_id), Some(root_id));
+        assert_eq!(tree.parent(root_id), None);
+    }
+
+    #[test]
+    fn test_to_nodes_round_trip() {
+        let (tree, _) = sample();
+        let nodes = tree.to_nodes();
+
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[0].label, "Root");
+        assert!(nodes[0].open);
+        assert_eq!(nodes[0].children[0].children[0].label, "Leaf");
+        assert_eq!(Tree::from_nodes(nodes).len(), tree.len());
+    }
+
+    #[test]
+    fn test_add_child() {
+        let (mut tree, root) = sample();
+
+        assert!(tree.add_child(root, Node::new("Added", false, vec![])));
+
+        assert_eq!(labels(&tree, root), ["Branch", "Other", "Added"]);
+    }
+
+    #[test]
+    fn test_add_child_to_missing_parent() {
+        let (mut tree, _) = sample();
+        let missing = Node::new("Missing", false, vec![]).id();
+
+        assert!(!tree.add_child(missing, Node::new("Orphan", false, vec![])));
+        assert_eq!(tree.len(), 5);
+    }
+
+    #[test]
+    fn test_insert_child_at_beginning_and_middle() {
+        let (mut tree, root) = sample();
+
+        tree.insert_child(root, 0, Node::new("First", false, vec![]));
+        tree.insert_child(root, 2, Node::new("Middle", false, vec![]));
+
+        assert_eq!(labels(&tree, root), ["First", "Branch", "Middle", "Other"]);
+    }
+
+    #[test]
+    fn test_insert_child_beyond_length() {
+        let (mut tree, root) = sample();
+
+        tree.insert_child(root, 10, Node::new("Last", false, vec![]));
+
+        assert_eq!(labels(&tree, root), ["Branch", "Other", "Last"]);
+    }
+
+    #[test]
+    fn test_remove_child_returns_subtree() {
+        let (mut tree, root) = sample();
+
+        let removed = tree.remove_child(root, 0).unwrap();
+
+        assert_eq!(removed.label, "Branch");
+        assert_eq!(removed.children[0].label, "Leaf");
+        assert_eq!(labels(&tree, root), ["Other"]);
+        assert_eq!(tree.len(), 3);
+        assert!(!tree.contains(removed.children[0].id()));
+    }
+
+    #[test]
+    fn test_remove_child_invalid_index() {
+        let (mut tree, root) = sample();
+
+        assert!(tree.remove_child(root, 5).is_none());
+        assert_eq!(tree.len(), 5);
+    }
+
+    #[test]
+    fn test_remove_last_child_then_append() {
+        let (mut tree, root) = sample();
+
+        tree.remove_child(root, 1);
+        tree.add_child(root, Node::new("Appended", false, vec![]));
+
+        assert_eq!(labels(&tree, root), ["Branch", "Appended"]);
+    }
+
+    #[test]
+    fn test_remove_root() {
+        let (mut tree, root) = sample();
+        let second = tree.roots().nth(1).unwrap();
+        let third = tree.add_root(Node::new("Third", false, vec![])).unwrap();
+
+        let removed = tree.remove(root).unwrap();
+
+        assert_eq!(removed.children.len(), 2);
+        assert_eq!(tree.roots().collect::<Vec<_>>(), vec![second, third]);
+        assert_eq!(tree.len(), 2);
+    }
+
+    #[test]
+    fn test_ids_already_stored_are_refused() {
+        let (mut tree, root) = sample();
+        let snapshot = tree.to_nodes();
+
+        assert_eq!(tree.add_root(snapshot[0].clone()), None);
+        assert!(!tree.add_child(root, snapshot[0].children[0].clone()));
+        assert!(!tree.insert_child(root, 0, snapshot[0].children[1].clone()));
+        assert_eq!(tree.len(), 5);
+
+        tree.remove(root);
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree.roots().count(), 1);
+        assert_eq!(tree.add_root(snapshot[0].clone()), Some(root));
+        assert_eq!(tree.len(), 5);
+    }
+
+    #[test]
+    fn test_freed_slots_are_reused() {
+        let (mut tree, root) = sample();
+        let slots = tree.slots.len();
+
+        tree.remove_child(root, 0);
+        tree.add_child(root, Node::new("Reused", false, vec![]));
+
+        assert_eq!(tree.slots.len(), slots);
+    }
+
+    #[test]
+    fn test_get_mut_updates_entry() {
+        let (mut tree, root) = sample();
+
+        tree.get_mut(root).unwrap().label = "Renamed".to_string();
+        tree.get_mut(root).unwrap().open = false;
+
+        assert_eq!(tree.get(root).unwrap().label, "Renamed");
+        assert!(!tree.get(root).unwrap().open);
+    }
+
+    #[test]
+    fn test_deep_tree_does_not_recurse() {
+        const DEPTH: usize = 100_000;
+        let root = Node::new("0", false, vec![]);
+        let root_id = root.id();
+        let mut tree = Tree::from_nodes(vec![root]);
+        let mut parent = root_id;
+        for depth in 1..DEPTH {
+            let child = Node::new(&depth.to_string(), false, vec![]);
+            let id = child.id();
+            tree.add_child(parent, child);
+            parent = id;
+        }
+
+        let nodes = tree.to_nodes();
+        let removed = tree.remove(root_id).unwrap();
+        assert!(tree.is_empty());
+        let rebuilt = Tree::from_nodes(vec![removed]);
+        assert_eq!(rebuilt.len(), DEPTH);
+        assert_eq!(rebuilt.roots().collect::<Vec<_>>(), [root_id]);
+        assert_eq!(rebuilt.get(parent).unwrap().label, (DEPTH - 1).to_string());
+
+        // Dropping a nested node recurses, so take the chain apart first
+        for mut node in nodes {
+            while let Some(child) = node.children.pop() {
+                node = child;
+            }
+        }
+    }
+
+    #[test]
+    fn test_payload_moves_in_and_out() {
+        let child = Node::with_data("Child", vec![1, 2], false, vec![]);
+        let child_id = child.id();
+        let root = Node::with_data("Root", vec![], true, vec![child]);
+        let root_id = root.id();
+        let mut tree = Tree::from_nodes(vec![root]);
+
+        tree.get_mut(child_id).unwrap().data.push(3);
+        let removed = tree.remove_child(root_id, 0).unwrap();
+
+        assert_eq!(removed.data, vec![1, 2, 3]);
+        assert!(tree.get(root_id).unwrap().data.is_empty());
+    }
+}
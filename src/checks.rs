@@ -0,0 +1,291 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::tree::{self, Node, NodeId};
+
+/// What a node's checkbox shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckState {
+    Unchecked,
+    Checked,
+    /// Some of the node's descendants are checked, but not all
+    Indeterminate,
+}
+
+/// Tri-state checkboxes for the nodes of a forest, kept apart from the tree
+/// like [`Selection`](crate::Selection).
+///
+/// A node with checked descendants takes its state from its children: it
+/// is checked when all of them are, and indeterminate when they are mixed.
+/// Otherwise its stored state decides, so a checked node stays checked when
+/// children are added below it, until one of them is toggled. The states
+/// therefore stay consistent when nodes are added, removed or moved: adding
+/// an unchecked child to a node whose children are checked makes it
+/// indeterminate, and undoing a removal brings the node back with its
+/// checkbox as it was.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Checks {
+    checked: HashSet<NodeId>,
+}
+
+impl Checks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether no node is checked
+    pub fn is_empty(&self) -> bool {
+        self.checked.is_empty()
+    }
+
+    /// The node's state, derived from its children when any of its
+    /// descendants is checked
+    pub fn state<T>(&self, node: &Node<T>) -> CheckState {
+        match self.derive(node) {
+            Some(state) => state,
+            None if self.checked.contains(&node.id()) => CheckState::Checked,
+            None => CheckState::Unchecked,
+        }
+    }
+
+    /// The state of every node in the forest, worked out in one walk rather
+    /// than one per node as with [`Checks::state`]
+    pub fn states<T>(&self, roots: &[Node<T>]) -> HashMap<NodeId, CheckState> {
+        let mut states = HashMap::new();
+        for root in roots {
+            self.collect(root, &mut states);
+        }
+        states
+    }
+
+    /// Checks or unchecks a node together with all of its descendants.
+    /// Ancestors follow from their children.
+    pub fn set<T>(&mut self, roots: &[Node<T>], id: NodeId, checked: bool) {
+        let Some(path) = tree::path_of(roots, id) else {
+            return;
+        };
+        if let Some(node) = tree::get(roots, &path) {
+            self.set_subtree(node, checked);
+        }
+
+        // Ancestors remember their state for when their children are gone.
+        // It comes from the children alone, so unchecking the last checked
+        // child of a node also unchecks the node. Each ancestor reuses the
+        // state worked out for the one below it, so only the siblings along
+        // the path are walked.
+        let mut state = if checked {
+            CheckState::Checked
+        } else {
+            CheckState::Unchecked
+        };
+        for depth in (1..path.len()).rev() {
+            let Some(ancestor) = tree::get(roots, &path[..depth]) else {
+                continue;
+            };
+            let below = path[depth];
+            let derived = combine(ancestor.children.iter().enumerate().map(|(index, child)| {
+                if index == below {
+                    state
+                } else {
+                    self.state(child)
+                }
+            }));
+            self.mark(ancestor.id(), derived == Some(CheckState::Checked));
+            state = derived.unwrap_or(CheckState::Unchecked);
+        }
+    }
+
+    /// Unchecks a checked node, and checks an unchecked or indeterminate one
+    pub fn toggle<T>(&mut self, roots: &[Node<T>], id: NodeId) {
+        let Some(node) = tree::find(roots, id) else {
+            return;
+        };
+        let checked = self.state(node) != CheckState::Checked;
+        self.set(roots, id, checked);
+    }
+
+    /// Ids of the checked nodes, parents before their children
+    pub fn checked<T>(&self, roots: &[Node<T>]) -> Vec<NodeId> {
+        let states = self.states(roots);
+        let mut ids = Vec::new();
+        let mut stack: Vec<&Node<T>> = roots.iter().rev().collect();
+        while let Some(node) = stack.pop() {
+            match states.get(&node.id()) {
+                Some(CheckState::Checked) => ids.push(node.id()),
+                Some(CheckState::Indeterminate) => {}
+                Some(CheckState::Unchecked) | None => continue,
+            }
+            stack.extend(node.children.iter().rev());
+        }
+        ids
+    }
+
+    pub fn clear(&mut self) {
+        self.checked.clear();
+    }
+
+    /// The node's state as its children give it, or None when none of its
+    /// descendants is checked
+    fn derive<T>(&self, node: &Node<T>) -> Option<CheckState> {
+        combine(node.children.iter().map(|child| self.state(child)))
+    }
+
+    /// Records the states of a node and its descendants, returning the
+    /// node's own
+    fn collect<T>(&self, node: &Node<T>, states: &mut HashMap<NodeId, CheckState>) -> CheckState {
+        let children: Vec<_> = node
+            .children
+            .iter()
+            .map(|child| self.collect(child, states))
+            .collect();
+        let state = match combine(children) {
+            Some(state) => state,
+            None if self.checked.contains(&node.id()) => CheckState::Checked,
+            None => CheckState::Unchecked,
+        };
+        states.insert(node.id(), state);
+        state
+    }
+
+    fn set_subtree<T>(&mut self, node: &Node<T>, checked: bool) {
+        self.mark(node.id(), checked);
+        for child in &node.children {
+            self.set_subtree(child, checked);
+        }
+    }
+
+    fn mark(&mut self, id: NodeId, checked: bool) {
+        if checked {
+            self.checked.insert(id);
+        } else {
+            self.checked.remove(&id);
+        }
+    }
+}
+
+/// The state of a node with the given child states, or None when none of
+/// them is checked or indeterminate
+fn combine(children: impl IntoIterator<Item = CheckState>) -> Option<CheckState> {
+    let (mut checked, mut unchecked) = (false, false);
+    for child in children {
+        match child {
+            CheckState::Checked => checked = true,
+            CheckState::Unchecked => unchecked = true,
+            CheckState::Indeterminate => return Some(CheckState::Indeterminate),
+        }
+        if checked && unchecked {
+            return Some(CheckState::Indeterminate);
+        }
+    }
+    checked.then_some(CheckState::Checked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::tests::sample;
+
+    fn states(checks: &Checks, roots: &[Node]) -> Vec<CheckState> {
+        tree::visible_rows(roots)
+            .iter()
+            .map(|row| checks.state(row.node))
+            .collect()
+    }
+
+    #[test]
+    fn test_checking_a_parent_checks_descendants() {
+        let roots = sample();
+        let mut checks = Checks::new();
+
+        checks.set(&roots, roots[0].children[0].id(), true);
+
+        let branch = &roots[0].children[0];
+        assert_eq!(checks.state(branch), CheckState::Checked);
+        assert_eq!(checks.state(&branch.children[0]), CheckState::Checked);
+        assert_eq!(checks.state(&roots[0]), CheckState::Indeterminate);
+        assert_eq!(
+            checks.checked(&roots),
+            [branch.id(), branch.children[0].id()]
+        );
+    }
+
+    #[test]
+    fn test_mixed_children_are_indeterminate() {
+        let mut roots = sample();
+        roots[0].children[0].add_child(Node::new("Leaf 2", false, vec![]));
+        let mut checks = Checks::new();
+        let leaf = roots[0].children[0].children[0].id();
+
+        checks.toggle(&roots, leaf);
+        assert_eq!(
+            states(&checks, &roots),
+            [
+                CheckState::Indeterminate,
+                CheckState::Indeterminate,
+                CheckState::Unchecked,
+                CheckState::Unchecked
+            ]
+        );
+
+        // Toggling an indeterminate node checks everything below it
+        checks.toggle(&roots, roots[0].id());
+        assert_eq!(checks.state(&roots[0]), CheckState::Checked);
+        checks.toggle(&roots, roots[0].id());
+        assert!(checks.is_empty());
+    }
+
+    #[test]
+    fn test_states_follow_structural_changes() {
+        let mut roots = sample();
+        let mut checks = Checks::new();
+        checks.set(&roots, roots[0].id(), true);
+
+        roots[0].add_child(Node::new("New", false, vec![]));
+        assert_eq!(checks.state(&roots[0]), CheckState::Indeterminate);
+
+        let new = roots[0].remove_child(2).unwrap();
+        assert_eq!(checks.state(&roots[0]), CheckState::Checked);
+        assert_eq!(checks.state(&new), CheckState::Unchecked);
+
+        // A node left without children keeps its last state
+        roots[0].children[0].children.clear();
+        assert_eq!(checks.state(&roots[0].children[0]), CheckState::Checked);
+    }
+
+    #[test]
+    fn test_checked_leaf_stays_checked_when_given_children() {
+        let mut roots = sample();
+        let mut checks = Checks::new();
+        let other = roots[0].children[1].id();
+        checks.set(&roots, other, true);
+
+        roots[0].children[1].add_child(Node::new("New", false, vec![]));
+        let new = roots[0].children[1].children[0].id();
+        assert_eq!(checks.state(&roots[0].children[1]), CheckState::Checked);
+        assert_eq!(checks.checked(&roots), [other]);
+
+        // Once a child is toggled, the node follows its children again
+        checks.toggle(&roots, new);
+        assert_eq!(checks.checked(&roots), [other, new]);
+        checks.toggle(&roots, new);
+        assert_eq!(checks.state(&roots[0].children[1]), CheckState::Unchecked);
+        assert!(checks.is_empty());
+    }
+
+    #[test]
+    fn test_states_match_each_node() {
+        let mut roots = sample();
+        let mut checks = Checks::new();
+        checks.set(&roots, roots[0].children[0].children[0].id(), true);
+        checks.set(&roots, roots[0].children[1].id(), true);
+        roots[0].children[1].add_child(Node::new("New", false, vec![]));
+
+        let states = checks.states(&roots);
+
+        let mut nodes: Vec<_> = roots.iter().collect();
+        while let Some(node) = nodes.pop() {
+            assert_eq!(states[&node.id()], checks.state(node));
+            nodes.extend(&node.children);
+        }
+        assert_eq!(states.len(), 6);
+    }
+}
@@ -0,0 +1,235 @@
+use quick_xml::escape::{escape, unescape};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::outline::{self, Options, Style};
+use crate::tree::Node;
+
+/// Attribute of the HTML flavour that holds the nodes as JSON
+const ATTRIBUTE: &str = "data-iced-tree";
+
+/// Copied subtrees in the two flavours written to the system clipboard
+/// together: outline text for any application, and HTML listing the same
+/// nodes whose outer element carries them as JSON, so that another
+/// instance can paste them with their open state and payload.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Contents {
+    pub text: String,
+    /// None when the clipboard offers no HTML
+    pub html: Option<String>,
+}
+
+impl Contents {
+    /// Plain text without a structured flavour, as copied by other
+    /// applications
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            html: None,
+        }
+    }
+}
+
+/// Copied subtrees, kept as nodes for an exact round trip within the
+/// application besides the [`Contents`] written to the system clipboard.
+///
+/// On paste the nodes in the HTML flavour win, wherever they were copied.
+/// Without them, text that is still the text of the last copy pastes the
+/// stored nodes; anything else is parsed as an indented outline, as a
+/// Markdown list when every line is a list item.
+#[derive(Debug, Clone)]
+pub struct Clipboard<T = ()> {
+    nodes: Vec<Node<T>>,
+    text: String,
+}
+
+impl<T> Default for Clipboard<T> {
+    fn default() -> Self {
+        Self {
+            nodes: Vec::new(),
+            text: String::new(),
+        }
+    }
+}
+
+impl<T: Clone + Default + Serialize + DeserializeOwned> Clipboard<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Outline text of the last copy
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Stores copies of the given subtrees and returns both flavours, to be
+    /// written to the system clipboard. Fails, keeping the previous copy,
+    /// when a payload cannot be serialized to JSON.
+    pub fn copy<'a>(
+        &mut self,
+        nodes: impl IntoIterator<Item = &'a Node<T>>,
+    ) -> Result<Contents, serde_json::Error>
+    where
+        T: 'a,
+    {
+        let nodes: Vec<_> = nodes.into_iter().map(Node::duplicate).collect();
+        let html = html(&nodes)?;
+        self.text = outline::write(&nodes, &Options::default());
+        self.nodes = nodes;
+        Ok(Contents {
+            text: self.text.clone(),
+            html: Some(html),
+        })
+    }
+
+    /// Nodes to insert for contents read from the system clipboard. Every
+    /// call returns new nodes with fresh ids, so the same copy can be pasted
+    /// repeatedly. Fails when foreign text is not a consistently indented
+    /// outline.
+    pub fn paste(&self, contents: &Contents) -> Result<Vec<Node<T>>, outline::Error> {
+        if let Some(nodes) = contents.html.as_deref().and_then(nodes_in) {
+            Ok(nodes)
+        } else if !self.nodes.is_empty() && contents.text == self.text {
+            Ok(self.nodes.iter().map(Node::duplicate).collect())
+        } else {
+            let options = Options {
+                style: Style::detect(&contents.text),
+                ..Options::default()
+            };
+            outline::parse(&contents.text, &options)
+        }
+    }
+}
+
+/// Nested lists of the labels, with the nodes as JSON on the outer list
+fn html<T: Serialize>(nodes: &[Node<T>]) -> Result<String, serde_json::Error> {
+    fn list<T>(nodes: &[Node<T>], html: &mut String) {
+        html.push_str("<ul>");
+        for node in nodes {
+            html.push_str("<li>");
+            html.push_str(&escape(node.label.as_str()));
+            if !node.children.is_empty() {
+                list(&node.children, html);
+            }
+            html.push_str("</li>");
+        }
+        html.push_str("</ul>");
+    }
+
+    let json = serde_json::to_string(nodes)?;
+    let mut html = format!("<div {ATTRIBUTE}=\"{}\">", escape(json.as_str()));
+    list(nodes, &mut html);
+    html.push_str("</div>");
+    Ok(html)
+}
+
+/// The nodes carried by HTML from [`html`], or None for any other HTML.
+/// Platforms may wrap the HTML in a document of their own.
+fn nodes_in<T: Default + DeserializeOwned>(html: &str) -> Option<Vec<Node<T>>> {
+    let start = html.find(&format!("{ATTRIBUTE}=\""))? + ATTRIBUTE.len() + 2;
+    let end = start + html[start..].find('"')?;
+    let json = unescape(&html[start..end]).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::tests::{labels, sample};
+
+    #[test]
+    fn test_copy_writes_outline_text() {
+        let roots = sample();
+        let mut clipboard = Clipboard::new();
+
+        assert_eq!(
+            clipboard.copy(&roots).unwrap().text,
+            "Root\n  Branch\n    Leaf\n  Other\nSecond\n"
+        );
+        assert_eq!(
+            clipboard.text(),
+            "Root\n  Branch\n    Leaf\n  Other\nSecond\n"
+        );
+    }
+
+    #[test]
+    fn test_paste_own_copy_keeps_structure() {
+        let roots = sample();
+        let mut clipboard = Clipboard::new();
+
+        let contents = clipboard.copy([&roots[0]]).unwrap();
+        let text = Contents::text(contents.text);
+        let pasted = clipboard.paste(&text).unwrap();
+
+        assert_eq!(labels(&pasted), ["Root"]);
+        assert!(pasted[0].open);
+        assert_ne!(pasted[0].id(), roots[0].id());
+        assert_ne!(clipboard.paste(&text).unwrap()[0].id(), pasted[0].id());
+    }
+
+    #[test]
+    fn test_paste_foreign_text_parses_outline() {
+        let roots = sample();
+        let mut clipboard = Clipboard::new();
+        clipboard.copy([&roots[0]]).unwrap();
+
+        let pasted = clipboard.paste(&Contents::text("- One\n  - Two")).unwrap();
+
+        assert_eq!(labels(&pasted), ["One"]);
+        assert_eq!(labels(&pasted[0].children), ["Two"]);
+        assert!(!pasted[0].open);
+        let malformed = Contents::text("One\n   Two");
+        assert_eq!(clipboard.paste(&malformed).unwrap_err().line, 2);
+    }
+
+    #[test]
+    fn test_paste_html_from_another_instance() {
+        let mut roots = sample();
+        roots[0].children[0].label = "<Branch> & \"quotes\"".into();
+        let contents = Clipboard::new().copy([&roots[0]]).unwrap();
+        let html = contents.html.clone().unwrap();
+        assert!(html.contains("<li>&lt;Branch&gt; &amp; &quot;quotes&quot;<ul>"));
+
+        // A fresh clipboard has no stored nodes, like another instance
+        let pasted = Clipboard::<()>::new()
+            .paste(&Contents {
+                text: contents.text,
+                html: Some(format!("<html><body>{html}</body></html>")),
+            })
+            .unwrap();
+
+        assert_eq!(labels(&pasted), ["Root"]);
+        assert!(pasted[0].open);
+        assert_eq!(pasted[0].children[0].label, roots[0].children[0].label);
+        assert_ne!(pasted[0].id(), roots[0].id());
+
+        // Foreign HTML falls back to the text
+        let foreign = Contents {
+            text: "One".into(),
+            html: Some("<ul><li>One</li></ul>".into()),
+        };
+        assert_eq!(
+            labels(&Clipboard::<()>::new().paste(&foreign).unwrap()),
+            ["One"]
+        );
+    }
+
+    #[test]
+    fn test_copy_fails_for_payload_without_json_form() {
+        use std::collections::BTreeMap;
+
+        // JSON object keys must be strings
+        let mut clipboard = Clipboard::new();
+        let kept = Node::with_data("Kept", BTreeMap::new(), false, vec![]);
+        clipboard.copy([&kept]).unwrap();
+        let payload = BTreeMap::from([((1, 2), 3)]);
+        let node = Node::with_data("Pair", payload, false, vec![]);
+
+        assert!(clipboard.copy([&node]).is_err());
+        assert_eq!(clipboard.text(), "Kept\n");
+    }
+}
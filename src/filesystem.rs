@@ -0,0 +1,222 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::opml;
+use crate::tree::{Load, Node};
+
+/// Node payload of a tree built from the filesystem. Nodes without an
+/// entry, such as ones added by the user, are never read from disk.
+pub type Payload = Option<Entry>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Kind {
+    File,
+    Folder,
+}
+
+/// A file or folder shown as a node
+///
+/// The path is serialized as text, with parts that are not valid Unicode
+/// replaced by U+FFFD, so a tree holding such a path can still be saved.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    #[serde(serialize_with = "serialize_path")]
+    pub path: PathBuf,
+    pub kind: Kind,
+}
+
+impl Entry {
+    pub fn new(path: impl Into<PathBuf>, kind: Kind) -> Self {
+        Self {
+            path: path.into(),
+            kind,
+        }
+    }
+
+    pub fn is_folder(&self) -> bool {
+        self.kind == Kind::Folder
+    }
+
+    /// Icon drawn before the node's label
+    pub fn icon(&self, open: bool) -> &'static str {
+        match self.kind {
+            Kind::File => "📄",
+            Kind::Folder if open => "📂",
+            Kind::Folder => "📁",
+        }
+    }
+}
+
+/// Writes a path as text, replacing what is not valid Unicode
+fn serialize_path<S: serde::Serializer>(path: &Path, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&path.to_string_lossy())
+}
+
+/// Entries are not stored in OPML; outlines read from it carry none
+impl opml::Attributes for Payload {}
+
+/// Builds an open node for the directory at `path` with its entries read.
+///
+/// Subfolders are left unloaded; read them with [`read`] when they are
+/// opened.
+pub fn open(path: &Path) -> io::Result<Node<Payload>> {
+    let metadata = fs::metadata(path)?;
+    if !metadata.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotADirectory,
+            "not a directory",
+        ));
+    }
+
+    let mut root = node(path.to_owned(), Kind::Folder);
+    root.children = read_dir(path)?;
+    root.load = Load::Loaded;
+    root.open = true;
+    Ok(root)
+}
+
+/// Reads the entries of a folder off the async executor. Fails with a
+/// description of the error, e.g. "permission denied", to be shown on the
+/// folder's node. Needs the `tokio` feature, which runs iced on tokio.
+#[cfg(feature = "tokio")]
+pub async fn read(path: PathBuf) -> Result<Vec<Node<Payload>>, String> {
+    tokio::task::spawn_blocking(move || read_dir(&path))
+        .await
+        .map_err(|error| error.to_string())?
+        .map_err(|error| error.kind().to_string())
+}
+
+/// Reads the entries of a directory as closed nodes, with folders
+/// unloaded: folders first, each group ordered by name ignoring case.
+/// Entries that cannot be read are left out rather than failing the whole
+/// listing.
+pub fn read_dir(path: &Path) -> io::Result<Vec<Node<Payload>>> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(path)? {
+        let Ok(entry) = entry else {
+            continue;
+        };
+        let path = entry.path();
+        // Follows symbolic links, so a link to a folder can be expanded
+        let kind = if path.is_dir() {
+            Kind::Folder
+        } else {
+            Kind::File
+        };
+        entries.push((kind, path));
+    }
+
+    entries.sort_by_cached_key(|(kind, path)| sort_key(*kind, path));
+    Ok(entries
+        .into_iter()
+        .map(|(kind, path)| node(path, kind))
+        .collect())
+}
+
+/// Orders folders before files, then by name ignoring case
+pub(crate) fn sort_key(kind: Kind, path: &Path) -> (bool, String, String) {
+    let name = name(path);
+    (kind == Kind::File, name.to_lowercase(), name)
+}
+
+/// Closed node for an entry, labelled with its file name. Folders are
+/// unloaded.
+pub(crate) fn node(path: PathBuf, kind: Kind) -> Node<Payload> {
+    let label = name(&path);
+    let mut node = Node::with_data(&label, Some(Entry::new(path, kind)), false, Vec::new());
+    if kind == Kind::Folder {
+        node.load = Load::Unloaded;
+    }
+    node
+}
+
+/// Last component of a path, or the whole path for roots like `/`
+pub(crate) fn name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::tree::tests::labels;
+
+    /// Creates an empty file under `dir` along with the folders leading to it
+    pub(crate) fn touch(dir: &Path, relative: &str) -> PathBuf {
+        let path = dir.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "").unwrap();
+        path
+    }
+
+    #[test]
+    fn test_read_dir_sorts_folders_first() {
+        let temp = tempfile::tempdir().unwrap();
+        touch(temp.path(), "b.txt");
+        touch(temp.path(), "A.txt");
+        touch(temp.path(), "zeta/inner.txt");
+        touch(temp.path(), "Alpha/inner.txt");
+
+        let children = read_dir(temp.path()).unwrap();
+
+        assert_eq!(labels(&children), ["Alpha", "zeta", "A.txt", "b.txt"]);
+        assert!(children[0].data.as_ref().unwrap().is_folder());
+        assert!(!children[2].data.as_ref().unwrap().is_folder());
+        assert!(children.iter().all(|child| !child.open));
+    }
+
+    #[test]
+    fn test_open_leaves_subfolders_unloaded() {
+        let temp = tempfile::tempdir().unwrap();
+        touch(temp.path(), "docs/guide/intro.md");
+        touch(temp.path(), "notes.txt");
+
+        let root = open(temp.path()).unwrap();
+
+        assert!(root.open);
+        assert!(root.load.is_loaded());
+        assert_eq!(labels(&root.children), ["docs", "notes.txt"]);
+        assert_eq!(root.children[0].load, Load::Unloaded);
+        assert!(root.children[0].is_expandable());
+        assert!(root.children[0].children.is_empty());
+        assert!(root.children[1].load.is_loaded());
+        assert!(!root.children[1].is_expandable());
+    }
+
+    #[test]
+    fn test_unreadable_folder_fails() {
+        let temp = tempfile::tempdir().unwrap();
+
+        let error = read_dir(&temp.path().join("missing")).unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_entry_with_non_unicode_path_serializes() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = Path::new("notes").join(OsStr::from_bytes(b"caf\xe9.txt"));
+        let node = node(path, Kind::File);
+
+        let json = serde_json::to_string(&node).unwrap();
+        let read: Node<Payload> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(read.data.unwrap().path, Path::new("notes/caf\u{fffd}.txt"));
+    }
+
+    #[test]
+    fn test_open_rejects_files() {
+        let temp = tempfile::tempdir().unwrap();
+        touch(temp.path(), "plain.txt");
+
+        assert!(open(&temp.path().join("plain.txt")).is_err());
+    }
+}
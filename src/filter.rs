@@ -0,0 +1,191 @@
+use std::ops::Range;
+
+use crate::tree::{self, Node, VisibleRow};
+
+/// Narrows the rows of a tree down to nodes whose label contains a query,
+/// ignoring case.
+///
+/// Ancestors of matching nodes stay visible and are shown expanded for as
+/// long as the filter is active. Their stored `open` flags are left alone,
+/// so clearing the filter brings back the previous expansion.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filter {
+    query: String,
+}
+
+impl Filter {
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+        }
+    }
+
+    /// The text being searched for, as typed
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// An empty filter lets every row through
+    pub fn is_empty(&self) -> bool {
+        self.query.is_empty()
+    }
+
+    /// Byte range of the first occurrence of the query in `label`, compared
+    /// character by character ignoring case
+    pub fn find(&self, label: &str) -> Option<Range<usize>> {
+        if self.is_empty() {
+            return None;
+        }
+
+        label.char_indices().find_map(|(start, _)| {
+            let mut rest = label[start..].char_indices();
+            let mut end = start;
+            for wanted in self.query.chars() {
+                let (offset, c) = rest.next()?;
+                if !c.to_lowercase().eq(wanted.to_lowercase()) {
+                    return None;
+                }
+                end = start + offset + c.len_utf8();
+            }
+            Some(start..end)
+        })
+    }
+
+    pub fn matches<T>(&self, node: &Node<T>) -> bool {
+        self.find(&node.label).is_some()
+    }
+
+    /// The rows a tree view shows with this filter applied, in display
+    /// order.
+    ///
+    /// A node is kept when it matches or one of its descendants does. Nodes
+    /// with matching descendants are expanded to reveal them; an open
+    /// matching node shows all of its children, so a match can be browsed
+    /// further. An empty filter gives [`tree::visible_rows`].
+    pub fn rows<'a, T>(&self, roots: &'a [Node<T>]) -> Vec<VisibleRow<'a, T>> {
+        if self.is_empty() {
+            return tree::visible_rows(roots);
+        }
+
+        let mut rows = Vec::new();
+        self.walk(roots, 0, &mut rows);
+        rows
+    }
+
+    fn walk<'a, T>(&self, nodes: &'a [Node<T>], depth: usize, rows: &mut Vec<VisibleRow<'a, T>>) {
+        for node in nodes {
+            let start = rows.len();
+            rows.push(VisibleRow {
+                node,
+                depth,
+                open: false,
+            });
+
+            if node.open && self.matches(node) {
+                rows[start].open = true;
+                rows.extend(
+                    tree::visible_rows(&node.children)
+                        .into_iter()
+                        .map(|row| VisibleRow {
+                            depth: depth + 1 + row.depth,
+                            ..row
+                        }),
+                );
+                continue;
+            }
+
+            self.walk(&node.children, depth + 1, rows);
+            if rows.len() > start + 1 {
+                rows[start].open = true;
+            } else if !self.matches(node) {
+                rows.truncate(start);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<Node> {
+        vec![
+            Node::new(
+                "Projects",
+                false,
+                vec![
+                    Node::new(
+                        "Garden",
+                        false,
+                        vec![Node::new("Plant tomatoes", false, vec![])],
+                    ),
+                    Node::new("Taxes", false, vec![]),
+                ],
+            ),
+            Node::new(
+                "Tomato recipes",
+                true,
+                vec![Node::new("Soup", false, vec![])],
+            ),
+            Node::new("Errands", false, vec![]),
+        ]
+    }
+
+    fn labels<'a>(rows: &[VisibleRow<'a, ()>]) -> Vec<(&'a str, usize, bool)> {
+        rows.iter()
+            .map(|row| (row.node.label.as_str(), row.depth, row.open))
+            .collect()
+    }
+
+    #[test]
+    fn test_find_ignores_case() {
+        let filter = Filter::new("TOM");
+
+        assert_eq!(filter.find("Plant tomatoes"), Some(6..9));
+        assert_eq!(filter.find("Taxes"), None);
+        assert_eq!(Filter::new("ÄB").find("xäbx"), Some(1..4));
+        assert_eq!(Filter::default().find("anything"), None);
+    }
+
+    #[test]
+    fn test_rows_keep_ancestors_expanded() {
+        let roots = sample();
+
+        let rows = Filter::new("tom").rows(&roots);
+
+        assert_eq!(
+            labels(&rows),
+            [
+                ("Projects", 0, true),
+                ("Garden", 1, true),
+                ("Plant tomatoes", 2, false),
+                ("Tomato recipes", 0, true),
+                ("Soup", 1, false),
+            ]
+        );
+        // The stored flags are untouched
+        assert!(!roots[0].open);
+        assert!(!roots[0].children[0].open);
+    }
+
+    #[test]
+    fn test_rows_hide_unmatched_children_of_closed_matches() {
+        let roots = sample();
+
+        assert_eq!(
+            labels(&Filter::new("projects").rows(&roots)),
+            [("Projects", 0, false)]
+        );
+        assert!(Filter::new("nothing").rows(&roots).is_empty());
+    }
+
+    #[test]
+    fn test_empty_filter_shows_visible_rows() {
+        let roots = sample();
+
+        assert_eq!(
+            labels(&Filter::default().rows(&roots)),
+            labels(&tree::visible_rows(&roots))
+        );
+    }
+}
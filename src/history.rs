@@ -0,0 +1,499 @@
+use std::collections::VecDeque;
+
+use crate::tree::{self, Node, NodeId};
+
+/// Number of transactions kept by [`History::new`]
+pub const DEFAULT_LIMIT: usize = 100;
+
+/// A single reversible change to a forest of nodes
+#[derive(Debug, Clone)]
+pub enum Edit<T = ()> {
+    /// Expands or collapses a node
+    SetOpen { id: NodeId, open: bool },
+    /// Replaces a node's label
+    Rename { id: NodeId, label: String },
+    /// Inserts a subtree under `parent`, or among the roots when it is None.
+    /// An index past the end appends.
+    Insert {
+        parent: Option<NodeId>,
+        index: usize,
+        node: Node<T>,
+    },
+    /// Removes a node together with its subtree. Nodes of the subtree
+    /// that are still loading are put back unloaded when it is undone.
+    Remove { id: NodeId },
+    /// Moves a node with its subtree, see [`tree::move_node`]
+    Move {
+        id: NodeId,
+        parent: Option<NodeId>,
+        index: usize,
+    },
+}
+
+impl<T> Edit<T> {
+    /// Applies the edit and returns the edit that reverses it, or None when
+    /// it does not apply (unknown id) and the forest is left untouched
+    pub fn apply(self, roots: &mut Vec<Node<T>>) -> Option<Edit<T>> {
+        match self {
+            Edit::SetOpen { id, open } => {
+                let node = tree::find_mut(roots, id)?;
+                let open = std::mem::replace(&mut node.open, open);
+                Some(Edit::SetOpen { id, open })
+            }
+            Edit::Rename { id, label } => {
+                let node = tree::find_mut(roots, id)?;
+                let label = std::mem::replace(&mut node.label, label);
+                Some(Edit::Rename { id, label })
+            }
+            Edit::Insert {
+                parent,
+                index,
+                node,
+            } => {
+                let id = node.id();
+                let siblings = match parent {
+                    Some(parent) => &mut tree::find_mut(roots, parent)?.children,
+                    None => roots,
+                };
+                siblings.insert(index.min(siblings.len()), node);
+                Some(Edit::Remove { id })
+            }
+            Edit::Remove { id } => {
+                let path = tree::path_of(roots, id)?;
+                let (&index, parent_path) = path.split_last()?;
+                let (parent, mut node) = if parent_path.is_empty() {
+                    (None, roots.remove(index))
+                } else {
+                    let parent = tree::get_mut(roots, parent_path)?;
+                    (Some(parent.id()), parent.children.remove(index))
+                };
+                // Results of reads still in flight are dropped once the
+                // node is gone, so it comes back unloaded
+                node.cancel_loads();
+                Some(Edit::Insert {
+                    parent,
+                    index,
+                    node,
+                })
+            }
+            Edit::Move { id, parent, index } => {
+                let (parent, index) = tree::move_node(roots, id, parent, index)?;
+                Some(Edit::Move { id, parent, index })
+            }
+        }
+    }
+}
+
+/// Undo/redo stacks of edit transactions.
+///
+/// Every recorded edit stores its inverse; undoing a transaction applies the
+/// inverses in reverse order, which in turn yields the edits needed to redo
+/// it. Only the most recent `limit` transactions are kept.
+#[derive(Debug, Clone)]
+pub struct History<T = ()> {
+    /// Transactions with the serial number they were recorded under
+    undo: VecDeque<(u64, Vec<Edit<T>>)>,
+    redo: Vec<(u64, Vec<Edit<T>>)>,
+    /// Inverses collected by the open transaction
+    pending: Vec<Edit<T>>,
+    /// Nesting depth of `begin` calls
+    depth: usize,
+    limit: usize,
+    next_serial: u64,
+    /// Revision of the state below the oldest kept transaction: the serial
+    /// of the last transaction dropped for the limit, or 0 if none was
+    base: u64,
+}
+
+impl<T> Default for History<T> {
+    fn default() -> Self {
+        Self::with_limit(DEFAULT_LIMIT)
+    }
+}
+
+impl<T> History<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a history keeping at most `limit` undoable transactions
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            pending: Vec::new(),
+            depth: 0,
+            limit,
+            next_serial: 1,
+            base: 0,
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty() || !self.pending.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Identifies the state the recorded edits lead to. Undoing back to a
+    /// state yields its revision again, so comparing against the revision
+    /// taken when a document was saved tells whether it has changed since.
+    /// Transactions dropped for the limit keep their revision, so undoing
+    /// as far as possible does not return to the state before them.
+    pub fn revision(&self) -> u64 {
+        self.undo.back().map_or(self.base, |(serial, _)| *serial)
+    }
+
+    /// Applies an edit and records its inverse. Outside a transaction the
+    /// edit is undone on its own. Returns false if the edit did not apply.
+    pub fn apply(&mut self, roots: &mut Vec<Node<T>>, edit: Edit<T>) -> bool {
+        let Some(inverse) = edit.apply(roots) else {
+            return false;
+        };
+        self.redo.clear();
+        self.pending.push(inverse);
+        if self.depth == 0 {
+            self.flush();
+        }
+        true
+    }
+
+    /// Starts grouping edits so they are undone and redone together.
+    /// Calls may nest; the group closes at the outermost [`History::commit`].
+    pub fn begin(&mut self) {
+        self.depth += 1;
+    }
+
+    /// Closes the group opened by the matching [`History::begin`]
+    pub fn commit(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+        if self.depth == 0 {
+            self.flush();
+        }
+    }
+
+    /// Reverts the most recent transaction, closing any open one first.
+    /// Returns false when there is nothing to undo.
+    pub fn undo(&mut self, roots: &mut Vec<Node<T>>) -> bool {
+        self.depth = 0;
+        self.flush();
+        let Some((serial, transaction)) = self.undo.pop_back() else {
+            return false;
+        };
+        self.redo.push((serial, replay(roots, transaction)));
+        true
+    }
+
+    /// Re-applies the most recently undone transaction.
+    /// Returns false when there is nothing to redo.
+    pub fn redo(&mut self, roots: &mut Vec<Node<T>>) -> bool {
+        let Some((serial, transaction)) = self.redo.pop() else {
+            return false;
+        };
+        let inverse = replay(roots, transaction);
+        self.push(serial, inverse);
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.pending.clear();
+        self.depth = 0;
+        self.base = 0;
+    }
+
+    /// Moves the open transaction onto the undo stack
+    fn flush(&mut self) {
+        let transaction = std::mem::take(&mut self.pending);
+        if !transaction.is_empty() {
+            let serial = self.next_serial;
+            self.next_serial += 1;
+            self.push(serial, transaction);
+        }
+    }
+
+    fn push(&mut self, serial: u64, transaction: Vec<Edit<T>>) {
+        self.undo.push_back((serial, transaction));
+        while self.undo.len() > self.limit {
+            if let Some((serial, _)) = self.undo.pop_front() {
+                self.base = serial;
+            }
+        }
+    }
+}
+
+/// Applies a transaction's edits last to first, collecting their inverses
+fn replay<T>(roots: &mut Vec<Node<T>>, transaction: Vec<Edit<T>>) -> Vec<Edit<T>> {
+    transaction
+        .into_iter()
+        .rev()
+        .filter_map(|edit| edit.apply(roots))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::Load;
+    use crate::tree::tests::{labels, sample};
+
+    fn id(roots: &[Node], path: &[usize]) -> NodeId {
+        tree::get(roots, path).unwrap().id()
+    }
+
+    #[test]
+    fn test_undo_redo_rename() {
+        let mut roots = sample();
+        let mut history = History::new();
+        let root = id(&roots, &[0]);
+
+        history.apply(
+            &mut roots,
+            Edit::Rename {
+                id: root,
+                label: "Renamed".into(),
+            },
+        );
+        assert_eq!(roots[0].label, "Renamed");
+
+        assert!(history.undo(&mut roots));
+        assert_eq!(roots[0].label, "Root");
+        assert!(!history.can_undo());
+
+        assert!(history.redo(&mut roots));
+        assert_eq!(roots[0].label, "Renamed");
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn test_undo_remove_restores_subtree_and_ids() {
+        let mut roots = sample();
+        let mut history = History::new();
+        let branch = id(&roots, &[0, 0]);
+        let leaf = id(&roots, &[0, 0, 0]);
+
+        history.apply(&mut roots, Edit::Remove { id: branch });
+        assert!(tree::find(&roots, leaf).is_none());
+
+        history.undo(&mut roots);
+        assert_eq!(tree::path_of(&roots, branch), Some(vec![0, 0]));
+        assert_eq!(tree::path_of(&roots, leaf), Some(vec![0, 0, 0]));
+    }
+
+    #[test]
+    fn test_undo_remove_leaves_loading_nodes_unloaded() {
+        let mut roots = sample();
+        let mut history = History::new();
+        let branch = id(&roots, &[0, 0]);
+        tree::find_mut(&mut roots, branch).unwrap().load = Load::Loading;
+
+        history.apply(&mut roots, Edit::Remove { id: branch });
+        history.undo(&mut roots);
+
+        let branch = tree::find(&roots, branch).unwrap();
+        assert_eq!(branch.load, Load::Unloaded);
+        assert!(branch.load.can_start());
+    }
+
+    #[test]
+    fn test_cloned_history_restores_original_ids() {
+        let mut roots = sample();
+        let mut history = History::new();
+        let branch = id(&roots, &[0, 0]);
+        history.apply(&mut roots, Edit::Remove { id: branch });
+
+        let mut copy = history.clone();
+        copy.undo(&mut roots);
+        assert_eq!(tree::path_of(&roots, branch), Some(vec![0, 0]));
+
+        copy.apply(
+            &mut roots,
+            Edit::Rename {
+                id: branch,
+                label: "Renamed".into(),
+            },
+        );
+        copy.undo(&mut roots);
+        assert_eq!(roots[0].children[0].label, "Branch");
+    }
+
+    #[test]
+    fn test_remove_and_insert_roots() {
+        let mut roots = sample();
+        let mut history = History::new();
+        let first = id(&roots, &[0]);
+
+        history.apply(&mut roots, Edit::Remove { id: first });
+        history.apply(
+            &mut roots,
+            Edit::Insert {
+                parent: None,
+                index: usize::MAX,
+                node: Node::new("Third", false, vec![]),
+            },
+        );
+        assert_eq!(labels(&roots), ["Second", "Third"]);
+
+        history.undo(&mut roots);
+        history.undo(&mut roots);
+        assert_eq!(roots[0].id(), first);
+        assert_eq!(roots.len(), 2);
+    }
+
+    #[test]
+    fn test_transaction_undone_as_one() {
+        let mut roots = sample();
+        let mut history = History::new();
+        let branch = id(&roots, &[0, 1]);
+
+        history.begin();
+        history.apply(
+            &mut roots,
+            Edit::Insert {
+                parent: Some(branch),
+                index: 0,
+                node: Node::new("New Node", false, vec![]),
+            },
+        );
+        history.apply(
+            &mut roots,
+            Edit::SetOpen {
+                id: branch,
+                open: true,
+            },
+        );
+        history.commit();
+        assert_eq!(tree::visible_rows(&roots).len(), 5);
+
+        history.undo(&mut roots);
+        assert_eq!(tree::visible_rows(&roots).len(), 4);
+        assert!(!history.can_undo());
+
+        history.redo(&mut roots);
+        assert_eq!(tree::visible_rows(&roots).len(), 5);
+    }
+
+    #[test]
+    fn test_undo_move_restores_position() {
+        let mut roots = sample();
+        let branch = id(&roots, &[0, 0]);
+        let target = id(&roots, &[1]);
+        let mut history = History::new();
+
+        history.apply(
+            &mut roots,
+            Edit::Move {
+                id: branch,
+                parent: Some(target),
+                index: 0,
+            },
+        );
+        assert_eq!(tree::path_of(&roots, branch), Some(vec![1, 0]));
+
+        history.undo(&mut roots);
+        assert_eq!(tree::path_of(&roots, branch), Some(vec![0, 0]));
+
+        history.redo(&mut roots);
+        assert_eq!(tree::path_of(&roots, branch), Some(vec![1, 0]));
+    }
+
+    #[test]
+    fn test_revision_returns_after_undo() {
+        let mut roots = sample();
+        let mut history = History::new();
+        let root = id(&roots, &[1]);
+
+        history.apply(&mut roots, Edit::SetOpen { id: root, open: true });
+        let saved = history.revision();
+
+        history.apply(&mut roots, Edit::SetOpen { id: root, open: false });
+        assert_ne!(history.revision(), saved);
+
+        history.undo(&mut roots);
+        assert_eq!(history.revision(), saved);
+
+        history.redo(&mut roots);
+        history.undo(&mut roots);
+        history.apply(&mut roots, Edit::SetOpen { id: root, open: false });
+        assert_ne!(history.revision(), saved);
+    }
+
+    #[test]
+    fn test_new_edit_clears_redo() {
+        let mut roots = sample();
+        let mut history = History::new();
+        let root = id(&roots, &[1]);
+
+        history.apply(&mut roots, Edit::SetOpen { id: root, open: true });
+        history.undo(&mut roots);
+        assert!(history.can_redo());
+
+        history.apply(
+            &mut roots,
+            Edit::Rename {
+                id: root,
+                label: "Other".into(),
+            },
+        );
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn test_failed_edit_is_not_recorded() {
+        let mut roots = sample();
+        let mut history = History::new();
+        let removed = id(&roots, &[1]);
+        roots.pop();
+
+        assert!(!history.apply(&mut roots, Edit::Remove { id: removed }));
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn test_limit_drops_oldest() {
+        let mut roots = sample();
+        let mut history = History::with_limit(2);
+        let root = id(&roots, &[0]);
+
+        for label in ["A", "B", "C"] {
+            history.apply(
+                &mut roots,
+                Edit::Rename {
+                    id: root,
+                    label: label.into(),
+                },
+            );
+        }
+
+        assert!(history.undo(&mut roots));
+        assert!(history.undo(&mut roots));
+        assert!(!history.undo(&mut roots));
+        assert_eq!(roots[0].label, "A");
+    }
+
+    #[test]
+    fn test_revision_of_dropped_state_stays_changed() {
+        let mut roots = sample();
+        let mut history = History::with_limit(2);
+        let root = id(&roots, &[0]);
+        let saved = history.revision();
+
+        for label in ["A", "B", "C"] {
+            history.apply(
+                &mut roots,
+                Edit::Rename {
+                    id: root,
+                    label: label.into(),
+                },
+            );
+        }
+        while history.undo(&mut roots) {}
+
+        // "A" is still applied, so this is not the state that was saved
+        assert_eq!(roots[0].label, "A");
+        assert_ne!(history.revision(), saved);
+    }
+}
@@ -0,0 +1,36 @@
+//! Tree view component for iced applications.
+//!
+//! [`Node`] is the owned tree model, [`Tree`] an arena-backed store for
+//! very large trees that is converted to nodes to be shown, and
+//! [`TreeView`] renders a forest of nodes while mapping user interaction
+//! into the application's own message type. [`History`]
+//! records reversible [`Edit`]s for undo and redo.
+
+pub mod arena;
+pub mod checks;
+pub mod clipboard;
+pub mod filesystem;
+pub mod filter;
+pub mod history;
+pub mod navigation;
+pub mod opml;
+pub mod outline;
+pub mod replace;
+pub mod search;
+pub mod selection;
+pub mod sort;
+pub mod tree;
+pub mod tree_view;
+pub mod watch;
+
+pub use arena::Tree;
+pub use checks::{CheckState, Checks};
+pub use clipboard::Clipboard;
+pub use filter::Filter;
+pub use history::{Edit, History};
+pub use navigation::{Navigation, Step};
+pub use replace::Replace;
+pub use search::Search;
+pub use selection::{SelectMode, Selection};
+pub use tree::{DropPosition, Load, Node, NodeId};
+pub use tree_view::TreeView;
@@ -0,0 +1,294 @@
+use iced::keyboard::Key;
+use iced::keyboard::key::Named;
+
+use crate::tree::{self, Node, NodeId, VisibleRow};
+
+/// Keyboard command that moves the focus cursor through a tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Navigation {
+    /// Previous visible row
+    Up,
+    /// Next visible row
+    Down,
+    /// Collapse an open node, otherwise move to its parent
+    Left,
+    /// Expand a closed node, otherwise move to its first child
+    Right,
+    /// First visible row
+    Home,
+    /// Last visible row
+    End,
+    /// One page of rows up
+    PageUp,
+    /// One page of rows down
+    PageDown,
+}
+
+impl Navigation {
+    /// Maps the arrow, Home/End and PageUp/PageDown keys to a command
+    pub fn from_key(key: &Key) -> Option<Self> {
+        let Key::Named(named) = key else {
+            return None;
+        };
+        Some(match named {
+            Named::ArrowUp => Self::Up,
+            Named::ArrowDown => Self::Down,
+            Named::ArrowLeft => Self::Left,
+            Named::ArrowRight => Self::Right,
+            Named::Home => Self::Home,
+            Named::End => Self::End,
+            Named::PageUp => Self::PageUp,
+            Named::PageDown => Self::PageDown,
+            _ => return None,
+        })
+    }
+}
+
+/// Outcome of a navigation command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// Move the cursor to this node
+    Focus(NodeId),
+    /// Expand this node, leaving the cursor on it
+    Open(NodeId),
+    /// Collapse this node, leaving the cursor on it
+    Close(NodeId),
+}
+
+/// Works out where a navigation command leads from the focused node.
+///
+/// `page` is the number of rows moved by PageUp/PageDown. When nothing is
+/// focused, or the focused node is hidden, the cursor lands on the first row
+/// (or the last one for `Up` and `End`). Returns None for an empty forest or
+/// when the cursor cannot move further.
+pub fn navigate<T>(
+    roots: &[Node<T>],
+    focused: Option<NodeId>,
+    navigation: Navigation,
+    page: usize,
+) -> Option<Step> {
+    navigate_rows(&tree::visible_rows(roots), focused, navigation, page)
+}
+
+/// Like [`navigate`], over rows that were already laid out, e.g. by a
+/// [`Filter`](crate::filter::Filter). A node that is only shown expanded
+/// because of the filter is never closed; Left moves to its parent instead.
+pub fn navigate_rows<T>(
+    rows: &[VisibleRow<'_, T>],
+    focused: Option<NodeId>,
+    navigation: Navigation,
+    page: usize,
+) -> Option<Step> {
+    let last = rows.len().checked_sub(1)?;
+    let focus = |index: usize| Some(Step::Focus(rows[index].node.id()));
+
+    let Some(current) = focused.and_then(|id| rows.iter().position(|row| row.node.id() == id))
+    else {
+        return match navigation {
+            Navigation::Up | Navigation::End => focus(last),
+            _ => focus(0),
+        };
+    };
+    let row = rows[current];
+    let node = row.node;
+
+    let target = match navigation {
+        Navigation::Up => current.checked_sub(1)?,
+        Navigation::Down => current + 1,
+        Navigation::Home => 0,
+        Navigation::End => last,
+        Navigation::PageUp => current.saturating_sub(page.max(1)),
+        Navigation::PageDown => current + page.max(1),
+        Navigation::Right => {
+            return match (node.is_expandable(), row.open) {
+                (false, _) => None,
+                (true, false) => Some(Step::Open(node.id())),
+                (true, true) => rows
+                    .get(current + 1)
+                    .filter(|child| child.depth > row.depth)
+                    .map(|child| Step::Focus(child.node.id())),
+            };
+        }
+        Navigation::Left => {
+            if row.open && node.open && node.is_expandable() {
+                return Some(Step::Close(node.id()));
+            }
+            return rows[..current]
+                .iter()
+                .rposition(|parent| parent.depth < row.depth)
+                .and_then(focus);
+        }
+    };
+
+    let target = target.min(last);
+    (target != current).then(|| focus(target)).flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::Filter;
+    use crate::tree::Load;
+
+    /// The crate's sample forest, with Other open on a leaf of its own
+    fn sample() -> Vec<Node> {
+        let mut roots = tree::tests::sample();
+        roots[0].children[1].add_child(Node::new("Leaf 2", false, vec![]));
+        roots[0].children[1].open = true;
+        roots
+    }
+
+    fn id(roots: &[Node], path: &[usize]) -> NodeId {
+        tree::get(roots, path).unwrap().id()
+    }
+
+    #[test]
+    fn test_from_key() {
+        assert_eq!(
+            Navigation::from_key(&Key::Named(Named::ArrowDown)),
+            Some(Navigation::Down)
+        );
+        assert_eq!(Navigation::from_key(&Key::Named(Named::F2)), None);
+        assert_eq!(Navigation::from_key(&Key::Character("j".into())), None);
+    }
+
+    #[test]
+    fn test_no_focus_starts_at_edges() {
+        let roots = sample();
+
+        assert_eq!(
+            navigate(&roots, None, Navigation::Down, 10),
+            Some(Step::Focus(id(&roots, &[0])))
+        );
+        assert_eq!(
+            navigate(&roots, None, Navigation::End, 10),
+            Some(Step::Focus(id(&roots, &[1])))
+        );
+        assert_eq!(
+            navigate(&Vec::<Node>::new(), None, Navigation::Down, 10),
+            None
+        );
+    }
+
+    #[test]
+    fn test_up_and_down_follow_visible_rows() {
+        let roots = sample();
+
+        // Branch is closed, so its leaf is skipped
+        assert_eq!(
+            navigate(&roots, Some(id(&roots, &[0, 0])), Navigation::Down, 10),
+            Some(Step::Focus(id(&roots, &[0, 1])))
+        );
+        assert_eq!(
+            navigate(&roots, Some(id(&roots, &[1])), Navigation::Up, 10),
+            Some(Step::Focus(id(&roots, &[0, 1, 0])))
+        );
+        assert_eq!(
+            navigate(&roots, Some(id(&roots, &[0])), Navigation::Up, 10),
+            None
+        );
+        assert_eq!(
+            navigate(&roots, Some(id(&roots, &[1])), Navigation::Down, 10),
+            None
+        );
+    }
+
+    #[test]
+    fn test_right_expands_then_enters() {
+        let roots = sample();
+
+        assert_eq!(
+            navigate(&roots, Some(id(&roots, &[0, 0])), Navigation::Right, 10),
+            Some(Step::Open(id(&roots, &[0, 0])))
+        );
+        assert_eq!(
+            navigate(&roots, Some(id(&roots, &[0, 1])), Navigation::Right, 10),
+            Some(Step::Focus(id(&roots, &[0, 1, 0])))
+        );
+        assert_eq!(
+            navigate(&roots, Some(id(&roots, &[1])), Navigation::Right, 10),
+            None
+        );
+    }
+
+    #[test]
+    fn test_right_opens_unloaded_node() {
+        let mut roots = sample();
+        roots[1].load = Load::Unloaded;
+
+        assert_eq!(
+            navigate(&roots, Some(id(&roots, &[1])), Navigation::Right, 10),
+            Some(Step::Open(id(&roots, &[1])))
+        );
+
+        // Open while loading: nothing to enter yet
+        roots[1].open = true;
+        roots[1].load = Load::Loading;
+        assert_eq!(
+            navigate(&roots, Some(id(&roots, &[1])), Navigation::Right, 10),
+            None
+        );
+    }
+
+    #[test]
+    fn test_left_collapses_then_goes_to_parent() {
+        let roots = sample();
+
+        assert_eq!(
+            navigate(&roots, Some(id(&roots, &[0, 1])), Navigation::Left, 10),
+            Some(Step::Close(id(&roots, &[0, 1])))
+        );
+        assert_eq!(
+            navigate(&roots, Some(id(&roots, &[0, 1, 0])), Navigation::Left, 10),
+            Some(Step::Focus(id(&roots, &[0, 1])))
+        );
+        assert_eq!(
+            navigate(&roots, Some(id(&roots, &[1])), Navigation::Left, 10),
+            None
+        );
+    }
+
+    #[test]
+    fn test_rows_expanded_by_filter() {
+        let roots = sample();
+        let rows = Filter::new("leaf").rows(&roots);
+        let branch = id(&roots, &[0, 0]);
+
+        // Branch is closed but shown expanded to reveal its leaf
+        assert_eq!(
+            navigate_rows(&rows, Some(branch), Navigation::Right, 10),
+            Some(Step::Focus(id(&roots, &[0, 0, 0])))
+        );
+        assert_eq!(
+            navigate_rows(&rows, Some(branch), Navigation::Left, 10),
+            Some(Step::Focus(id(&roots, &[0])))
+        );
+        assert_eq!(
+            navigate_rows(&rows, Some(id(&roots, &[0, 1])), Navigation::Left, 10),
+            Some(Step::Close(id(&roots, &[0, 1])))
+        );
+    }
+
+    #[test]
+    fn test_home_end_and_paging() {
+        let roots = sample();
+        let middle = Some(id(&roots, &[0, 0]));
+
+        assert_eq!(
+            navigate(&roots, middle, Navigation::Home, 10),
+            Some(Step::Focus(id(&roots, &[0])))
+        );
+        assert_eq!(
+            navigate(&roots, middle, Navigation::End, 10),
+            Some(Step::Focus(id(&roots, &[1])))
+        );
+        assert_eq!(
+            navigate(&roots, middle, Navigation::PageDown, 2),
+            Some(Step::Focus(id(&roots, &[0, 1, 0])))
+        );
+        assert_eq!(
+            navigate(&roots, middle, Navigation::PageUp, 10),
+            Some(Step::Focus(id(&roots, &[0])))
+        );
+    }
+}
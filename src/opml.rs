@@ -0,0 +1,368 @@
+use std::fmt;
+use std::iter::Peekable;
+use std::vec::IntoIter;
+
+use quick_xml::Writer;
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::reader::Reader;
+
+use crate::tree::{self, Node};
+
+/// Attribute marking an `<outline>` element whose children are shown
+pub const EXPANDED: &str = "_expanded";
+
+/// Node payload able to carry `<outline>` attributes other than `text` and
+/// [`EXPANDED`]. The unit payload drops them.
+pub trait Attributes: Default {
+    /// Stores an attribute read from an `<outline>` element
+    fn insert(&mut self, _name: &str, _value: &str) {}
+
+    /// Attributes to write back, in order
+    fn attributes(&self) -> Vec<(&str, &str)> {
+        Vec::new()
+    }
+}
+
+impl Attributes for () {}
+
+/// Keeps every unknown attribute in document order
+impl Attributes for Vec<(String, String)> {
+    fn insert(&mut self, name: &str, value: &str) {
+        self.push((name.to_string(), value.to_string()));
+    }
+
+    fn attributes(&self) -> Vec<(&str, &str)> {
+        self.iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect()
+    }
+}
+
+/// Why an OPML document could not be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The document is not well-formed XML; `position` is a byte offset
+    Xml { position: u64, message: String },
+    /// The document has no `<opml>` root with a `<body>`
+    NotOpml,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Xml { position, message } => write!(f, "byte {position}: {message}"),
+            Error::NotOpml => write!(f, "not an OPML document"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Reads the outlines in the body of an OPML document.
+///
+/// A node is open when its element has `_expanded="true"` or it is listed
+/// in the head's `<expansionState>`. Outlines without `text` get an empty
+/// label.
+pub fn read<T: Attributes>(opml: &str) -> Result<Vec<Node<T>>, Error> {
+    let mut reader = Reader::from_str(opml);
+    reader.config_mut().trim_text(true);
+
+    let mut roots = Vec::new();
+    // Outlines whose end tag has not been reached yet
+    let mut stack: Vec<Node<T>> = Vec::new();
+    let mut in_opml = false;
+    let mut in_body = false;
+    let mut in_expansion_state = false;
+    let mut expansion_state = String::new();
+
+    loop {
+        let event = reader.read_event().map_err(|error| Error::Xml {
+            position: reader.error_position(),
+            message: error.to_string(),
+        })?;
+        let xml_error = |error: &dyn fmt::Display| Error::Xml {
+            position: reader.buffer_position(),
+            message: error.to_string(),
+        };
+
+        match event {
+            Event::Start(element) if in_body && element.name().as_ref() == b"outline" => {
+                stack.push(outline(&element).map_err(|error| xml_error(&error))?);
+            }
+            Event::Empty(element) if in_body && element.name().as_ref() == b"outline" => {
+                let node = outline(&element).map_err(|error| xml_error(&error))?;
+                close(node, &mut stack, &mut roots);
+            }
+            Event::Start(element) => match element.name().as_ref() {
+                b"opml" => in_opml = true,
+                b"body" if in_opml => in_body = true,
+                b"expansionState" if in_opml => in_expansion_state = true,
+                _ => {}
+            },
+            Event::Empty(element) if element.name().as_ref() == b"body" && in_opml => {
+                in_body = true;
+                break;
+            }
+            Event::Text(text) if in_expansion_state => {
+                expansion_state.push_str(&text.decode().map_err(|error| xml_error(&error))?);
+            }
+            Event::End(element) => match element.name().as_ref() {
+                b"outline" if in_body => {
+                    if let Some(node) = stack.pop() {
+                        close(node, &mut stack, &mut roots);
+                    }
+                }
+                b"body" => break,
+                b"expansionState" => in_expansion_state = false,
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if !in_body {
+        return Err(Error::NotOpml);
+    }
+    while let Some(node) = stack.pop() {
+        close(node, &mut stack, &mut roots);
+    }
+
+    apply_expansion_state(&mut roots, &expansion_state);
+    Ok(roots)
+}
+
+/// Writes a forest as an OPML 2.0 document with the given title
+pub fn write<T: Attributes>(roots: &[Node<T>], title: &str) -> String {
+    fn outlines<T: Attributes>(writer: &mut Writer<Vec<u8>>, nodes: &[Node<T>]) {
+        for node in nodes {
+            let mut element = BytesStart::new("outline");
+            element.push_attribute(("text", node.label.as_str()));
+            if node.open && !node.children.is_empty() {
+                element.push_attribute((EXPANDED, "true"));
+            }
+            for attribute in node.data.attributes() {
+                element.push_attribute(attribute);
+            }
+
+            if node.children.is_empty() {
+                write_event(writer, Event::Empty(element));
+            } else {
+                write_event(writer, Event::Start(element));
+                outlines(writer, &node.children);
+                write_event(writer, Event::End(BytesEnd::new("outline")));
+            }
+        }
+    }
+
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+    write_event(
+        &mut writer,
+        Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)),
+    );
+    write_event(
+        &mut writer,
+        Event::Start(BytesStart::new("opml").with_attributes([("version", "2.0")])),
+    );
+    write_event(&mut writer, Event::Start(BytesStart::new("head")));
+    write_event(&mut writer, Event::Start(BytesStart::new("title")));
+    write_event(&mut writer, Event::Text(BytesText::new(title)));
+    write_event(&mut writer, Event::End(BytesEnd::new("title")));
+    write_event(&mut writer, Event::Start(BytesStart::new("expansionState")));
+    write_event(
+        &mut writer,
+        Event::Text(BytesText::new(&expansion_state(roots))),
+    );
+    write_event(&mut writer, Event::End(BytesEnd::new("expansionState")));
+    write_event(&mut writer, Event::End(BytesEnd::new("head")));
+    write_event(&mut writer, Event::Start(BytesStart::new("body")));
+    outlines(&mut writer, roots);
+    write_event(&mut writer, Event::End(BytesEnd::new("body")));
+    write_event(&mut writer, Event::End(BytesEnd::new("opml")));
+
+    let mut opml = String::from_utf8(writer.into_inner()).expect("writer only emits UTF-8");
+    opml.push('\n');
+    opml
+}
+
+/// Writing into a `Vec` cannot fail
+fn write_event(writer: &mut Writer<Vec<u8>>, event: Event<'_>) {
+    writer
+        .write_event(event)
+        .expect("writing to memory cannot fail");
+}
+
+/// Builds a closed node from an `<outline>` element's attributes
+fn outline<T: Attributes>(element: &BytesStart<'_>) -> Result<Node<T>, quick_xml::Error> {
+    let mut label = String::new();
+    let mut open = false;
+    let mut data = T::default();
+
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(quick_xml::Error::from)?;
+        let value = attribute.unescape_value()?;
+        match attribute.key.as_ref() {
+            b"text" => label = value.into_owned(),
+            key if key == EXPANDED.as_bytes() => open = value == "true",
+            key => data.insert(&String::from_utf8_lossy(key), &value),
+        }
+    }
+
+    Ok(Node::with_data(&label, data, open, Vec::new()))
+}
+
+/// Attaches a finished outline to its parent, or to the roots
+fn close<T>(node: Node<T>, stack: &mut [Node<T>], roots: &mut Vec<Node<T>>) {
+    match stack.last_mut() {
+        Some(parent) => parent.children.push(node),
+        None => roots.push(node),
+    }
+}
+
+/// Indices of the open nodes among the visible rows, the format of the
+/// OPML `<expansionState>` element
+fn expansion_state<T>(roots: &[Node<T>]) -> String {
+    tree::visible_rows(roots)
+        .iter()
+        .enumerate()
+        .filter(|(_, row)| row.node.open && !row.node.children.is_empty())
+        .map(|(index, _)| index.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Expands rows listed in an `<expansionState>`. Each index counts visible
+/// rows after the expansions at lower indices have been applied, so the
+/// sorted indices are matched in a single walk over the rows.
+fn apply_expansion_state<T>(roots: &mut [Node<T>], state: &str) {
+    fn expand<T>(nodes: &mut [Node<T>], row: &mut usize, indices: &mut Peekable<IntoIter<usize>>) {
+        for node in nodes {
+            if indices.peek().is_none() {
+                return;
+            }
+            if indices.next_if_eq(row).is_some() {
+                node.open = true;
+            }
+            *row += 1;
+            if node.open {
+                expand(&mut node.children, row, indices);
+            }
+        }
+    }
+
+    let mut indices: Vec<usize> = state
+        .split(',')
+        .filter_map(|index| index.trim().parse().ok())
+        .collect();
+    indices.sort_unstable();
+    indices.dedup();
+    expand(roots, &mut 0, &mut indices.into_iter().peekable());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::tests::{labels, sample};
+
+    #[test]
+    fn test_round_trip() {
+        let mut roots = sample();
+        // Characters that need escaping in attributes
+        roots[0].label = "Root & more".into();
+        roots[0].children[1].label = "Other \"quoted\"".into();
+
+        let opml = write(&roots, "Sample");
+        let read: Vec<Node> = read(&opml).unwrap();
+
+        assert_eq!(labels(&read), ["Root & more", "Second"]);
+        assert_eq!(labels(&read[0].children), ["Branch", "Other \"quoted\""]);
+        assert_eq!(labels(&read[0].children[0].children), ["Leaf"]);
+        assert!(read[0].open);
+        assert!(!read[0].children[0].open);
+    }
+
+    #[test]
+    fn test_write_format() {
+        let roots = vec![Node::new(
+            "Root",
+            true,
+            vec![Node::new("Child", false, vec![])],
+        )];
+
+        assert_eq!(
+            write(&roots, "Title"),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<opml version="2.0">
+  <head>
+    <title>Title</title>
+    <expansionState>0</expansionState>
+  </head>
+  <body>
+    <outline text="Root" _expanded="true">
+      <outline text="Child"/>
+    </outline>
+  </body>
+</opml>
+"#
+        );
+    }
+
+    #[test]
+    fn test_read_expansion_state_from_head() {
+        let opml = r#"<opml version="2.0">
+            <head><expansionState> 2,0,2,x,4 </expansionState></head>
+            <body>
+              <outline text="A"><outline text="A1"/><outline text="A2"><outline text="x"/></outline></outline>
+              <outline text="B"><outline text="B1"/></outline>
+            </body>
+          </opml>"#;
+
+        let roots: Vec<Node> = read(opml).unwrap();
+
+        // Row 0 is A; once it is open, row 2 is A2 and row 4 is B. Order,
+        // repeats and entries that are not numbers do not matter.
+        assert!(roots[0].open);
+        assert!(roots[0].children[1].open);
+        assert!(roots[1].open);
+    }
+
+    #[test]
+    fn test_unknown_attributes_kept_by_payload() {
+        let opml = r#"<opml><body>
+            <outline text="Link" type="link" url="https://example.com/?a=1&amp;b=2"/>
+          </body></opml>"#;
+
+        let roots: Vec<Node<Vec<(String, String)>>> = read(opml).unwrap();
+        assert_eq!(
+            roots[0].data,
+            [
+                ("type".to_string(), "link".to_string()),
+                (
+                    "url".to_string(),
+                    "https://example.com/?a=1&b=2".to_string()
+                )
+            ]
+        );
+
+        let written = write(&roots, "");
+        assert!(written.contains(
+            r#"<outline text="Link" type="link" url="https://example.com/?a=1&amp;b=2"/>"#
+        ));
+
+        // The unit payload drops them
+        let plain: Vec<Node> = read(opml).unwrap();
+        assert!(!write(&plain, "").contains("url="));
+    }
+
+    #[test]
+    fn test_read_errors() {
+        assert_eq!(
+            read::<()>("<html><body></body></html>").unwrap_err(),
+            Error::NotOpml
+        );
+        assert!(matches!(
+            read::<()>("<opml><body><outline text=\"a\"></body></opml>"),
+            Err(Error::Xml { .. })
+        ));
+    }
+}
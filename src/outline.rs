@@ -0,0 +1,332 @@
+use std::fmt;
+
+use crate::tree::Node;
+
+/// How nodes are written out, one per line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// Bare labels
+    Plain,
+    /// Markdown bullet list items (`- label`)
+    Markdown,
+}
+
+impl Style {
+    /// Markdown when every non-blank line of `text` is a list item, for
+    /// text of unknown origin such as the clipboard's
+    pub fn detect(text: &str) -> Self {
+        let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+        if lines.all(|line| strip_marker(line).is_some()) {
+            Style::Markdown
+        } else {
+            Style::Plain
+        }
+    }
+}
+
+/// Settings shared by [`parse`] and [`write`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    /// Spaces per nesting level. A tab always counts as one level.
+    pub indent: usize,
+    /// Indent written output with tabs instead of spaces
+    pub tabs: bool,
+    pub style: Style,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            indent: 2,
+            tabs: false,
+            style: Style::Plain,
+        }
+    }
+}
+
+impl Options {
+    pub fn markdown() -> Self {
+        Self {
+            style: Style::Markdown,
+            ..Self::default()
+        }
+    }
+}
+
+/// A line of an outline that could not be placed in the tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    /// 1-based line number
+    pub line: usize,
+    pub kind: ErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The indentation mixes tabs and spaces
+    MixedIndent,
+    /// The number of leading spaces is not a multiple of the indent width
+    UnevenIndent { spaces: usize, indent: usize },
+    /// The line is nested more than one level below the previous one
+    SkippedLevel { level: usize, expected: usize },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match self.kind {
+            ErrorKind::MixedIndent => write!(f, "indentation mixes tabs and spaces"),
+            ErrorKind::UnevenIndent { spaces, indent } => {
+                write!(
+                    f,
+                    "indentation of {spaces} spaces is not a multiple of {indent}"
+                )
+            }
+            ErrorKind::SkippedLevel { level, expected } => write!(
+                f,
+                "nested {level} levels deep where at most {expected} is possible"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Reads tab or space indented lines into a forest of closed nodes.
+///
+/// With [`Style::Markdown`], bullets (`-`, `*`, `+`) and numbered items
+/// (`1.`, `1)`) are stripped from the labels; plain labels are kept as they
+/// are, even when they start like a list item. Blank lines are skipped.
+/// Each line may be at most one level deeper than the previous one.
+pub fn parse<T: Default>(text: &str, options: &Options) -> Result<Vec<Node<T>>, Error> {
+    let mut roots = Vec::new();
+    // Nodes on the path to the previous line; the last one is its node
+    let mut stack: Vec<Node<T>> = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let content = line.trim_start();
+        if content.trim_end().is_empty() {
+            continue;
+        }
+        let error = |kind| Error {
+            line: index + 1,
+            kind,
+        };
+
+        let whitespace = &line[..line.len() - content.len()];
+        let tabs = whitespace.chars().filter(|&c| c == '\t').count();
+        let spaces = whitespace.len() - tabs;
+        let level = match (tabs, spaces) {
+            (0, spaces) if spaces % options.indent.max(1) == 0 => spaces / options.indent.max(1),
+            (0, spaces) => {
+                return Err(error(ErrorKind::UnevenIndent {
+                    spaces,
+                    indent: options.indent,
+                }));
+            }
+            (tabs, 0) => tabs,
+            _ => return Err(error(ErrorKind::MixedIndent)),
+        };
+        if level > stack.len() {
+            return Err(error(ErrorKind::SkippedLevel {
+                level,
+                expected: stack.len(),
+            }));
+        }
+
+        while stack.len() > level {
+            close(&mut stack, &mut roots);
+        }
+        let content = content.trim_end();
+        let label = match options.style {
+            Style::Plain => content,
+            Style::Markdown => strip_marker(content).unwrap_or(content),
+        };
+        stack.push(Node::with_data(label, T::default(), false, Vec::new()));
+    }
+
+    while !stack.is_empty() {
+        close(&mut stack, &mut roots);
+    }
+    Ok(roots)
+}
+
+/// Writes a forest one node per line, indented per nesting level
+pub fn write<T>(roots: &[Node<T>], options: &Options) -> String {
+    fn lines<T>(nodes: &[Node<T>], depth: usize, options: &Options, text: &mut String) {
+        for node in nodes {
+            if options.tabs {
+                text.extend(std::iter::repeat_n('\t', depth));
+            } else {
+                text.extend(std::iter::repeat_n(' ', depth * options.indent));
+            }
+            if options.style == Style::Markdown {
+                text.push_str("- ");
+            }
+            text.push_str(&node.label);
+            text.push('\n');
+            lines(&node.children, depth + 1, options, text);
+        }
+    }
+
+    let mut text = String::new();
+    lines(roots, 0, options, &mut text);
+    text
+}
+
+/// Removes a leading Markdown list marker, if any
+fn strip_marker(content: &str) -> Option<&str> {
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(label) = content.strip_prefix(bullet) {
+            return Some(label.trim_start());
+        }
+    }
+    if content == "-" || content == "*" || content == "+" {
+        return Some("");
+    }
+
+    let digits = content.len()
+        - content
+            .trim_start_matches(|c: char| c.is_ascii_digit())
+            .len();
+    if digits > 0 {
+        let rest = &content[digits..];
+        for marker in [". ", ") "] {
+            if let Some(label) = rest.strip_prefix(marker) {
+                return Some(label.trim_start());
+            }
+        }
+    }
+    None
+}
+
+/// Attaches the innermost open node to its parent, or to the roots
+fn close<T>(stack: &mut Vec<Node<T>>, roots: &mut Vec<Node<T>>) {
+    if let Some(node) = stack.pop() {
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => roots.push(node),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::tests::{labels, sample};
+
+    fn parse_default(text: &str) -> Result<Vec<Node>, Error> {
+        parse(text, &Options::default())
+    }
+
+    #[test]
+    fn test_write_plain_and_markdown() {
+        assert_eq!(
+            write(&sample(), &Options::default()),
+            "Root\n  Branch\n    Leaf\n  Other\nSecond\n"
+        );
+        assert_eq!(
+            write(&sample(), &Options::markdown()),
+            "- Root\n  - Branch\n    - Leaf\n  - Other\n- Second\n"
+        );
+        assert_eq!(
+            write(
+                &sample()[..1],
+                &Options {
+                    tabs: true,
+                    ..Options::default()
+                }
+            ),
+            "Root\n\tBranch\n\t\tLeaf\n\tOther\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_round_trips_both_styles() {
+        for options in [Options::default(), Options::markdown()] {
+            let text = write(&sample(), &options);
+            let roots: Vec<Node> = parse(&text, &options).unwrap();
+
+            assert_eq!(write(&roots, &options), text);
+        }
+    }
+
+    #[test]
+    fn test_parse_markdown_markers() {
+        let text = "* One\n  1. Two\n  2) Three\n    + Four\n\n- Five";
+        let roots: Vec<Node> = parse(text, &Options::markdown()).unwrap();
+
+        assert_eq!(labels(&roots), ["One", "Five"]);
+        assert_eq!(labels(&roots[0].children), ["Two", "Three"]);
+        assert_eq!(labels(&roots[0].children[1].children), ["Four"]);
+    }
+
+    #[test]
+    fn test_parse_tabs_and_custom_width() {
+        let tabs = parse_default("Root\n\tChild\n\t\tGrandchild").unwrap();
+        assert_eq!(labels(&tabs[0].children[0].children), ["Grandchild"]);
+
+        let options = Options {
+            indent: 4,
+            ..Options::default()
+        };
+        let wide: Vec<Node> = parse("Root\n    Child\n        Grandchild", &options).unwrap();
+        assert_eq!(labels(&wide[0].children[0].children), ["Grandchild"]);
+    }
+
+    #[test]
+    fn test_parse_keeps_labels_that_only_look_like_markers() {
+        let text = "-5 degrees\n2024 plans\n*bold*";
+        let roots: Vec<Node> = parse(text, &Options::markdown()).unwrap();
+
+        assert_eq!(labels(&roots), ["-5 degrees", "2024 plans", "*bold*"]);
+    }
+
+    #[test]
+    fn test_plain_round_trip_keeps_marker_like_labels() {
+        let roots = vec![
+            Node::new("1. Intro", false, vec![Node::new("- note", false, vec![])]),
+            Node::new("Summary", false, vec![]),
+        ];
+        let text = write(&roots, &Options::default());
+
+        let parsed = parse_default(&text).unwrap();
+
+        assert_eq!(labels(&parsed), ["1. Intro", "Summary"]);
+        assert_eq!(labels(&parsed[0].children), ["- note"]);
+        assert_eq!(Style::detect(&text), Style::Plain);
+        assert_eq!(Style::detect("- One\n  2. Two\n"), Style::Markdown);
+    }
+
+    #[test]
+    fn test_parse_errors_name_the_line() {
+        assert_eq!(
+            parse_default("Root\n   Child").unwrap_err(),
+            Error {
+                line: 2,
+                kind: ErrorKind::UnevenIndent {
+                    spaces: 3,
+                    indent: 2
+                }
+            }
+        );
+        assert_eq!(
+            parse_default("Root\n\n    Grandchild").unwrap_err(),
+            Error {
+                line: 3,
+                kind: ErrorKind::SkippedLevel {
+                    level: 2,
+                    expected: 1
+                }
+            }
+        );
+        assert_eq!(
+            parse_default("Root\n\t  Child").unwrap_err().kind,
+            ErrorKind::MixedIndent
+        );
+        assert_eq!(
+            parse_default("  Indented first").unwrap_err().to_string(),
+            "line 1: nested 1 levels deep where at most 0 is possible"
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use std::slice;
+
+use regex::{NoExpand, Regex};
+
+use crate::history::Edit;
+use crate::search::document_order;
+use crate::tree::{self, Node, NodeId};
+
+/// Rewrites the parts of node labels that match a literal text or a regular
+/// expression.
+///
+/// [`Replace::preview`] lists the labels that would change without
+/// touching the forest; its renames are applied through the history, so a
+/// replacement can be undone in one step when they are grouped in a
+/// transaction.
+#[derive(Debug, Clone)]
+pub struct Replace {
+    /// None when there is nothing to search for
+    regex: Option<Regex>,
+    replacement: String,
+    /// Whether `$1` and `${name}` in the replacement refer to capture groups
+    expand: bool,
+}
+
+/// A label change found by [`Replace::preview`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rename {
+    pub id: NodeId,
+    pub from: String,
+    pub to: String,
+}
+
+impl Rename {
+    /// The history edit that gives the node its new label
+    pub fn edit<T>(self) -> Edit<T> {
+        Edit::Rename {
+            id: self.id,
+            label: self.to,
+        }
+    }
+}
+
+impl Replace {
+    /// Replaces every occurrence of `find`, matching case, with
+    /// `replacement` taken as is
+    pub fn literal(find: &str, replacement: impl Into<String>) -> Self {
+        Self {
+            regex: (!find.is_empty())
+                .then(|| Regex::new(&regex::escape(find)).expect("escaped text is a valid regex")),
+            replacement: replacement.into(),
+            expand: false,
+        }
+    }
+
+    /// Replaces every match of `pattern`. In `replacement`, `$1` or
+    /// `${name}` stand for the text of a capture group and `$$` for a
+    /// dollar sign.
+    pub fn regex(pattern: &str, replacement: impl Into<String>) -> Result<Self, regex::Error> {
+        let regex = if pattern.is_empty() {
+            None
+        } else {
+            Some(Regex::new(pattern)?)
+        };
+        Ok(Self {
+            regex,
+            replacement: replacement.into(),
+            expand: true,
+        })
+    }
+
+    /// The new label for `label`, or None when the replacement leaves it
+    /// unchanged
+    pub fn apply(&self, label: &str) -> Option<String> {
+        let regex = self.regex.as_ref()?;
+        let replaced = if self.expand {
+            regex.replace_all(label, self.replacement.as_str())
+        } else {
+            regex.replace_all(label, NoExpand(&self.replacement))
+        };
+        (replaced != label).then(|| replaced.into_owned())
+    }
+
+    /// Labels the replacement would change, in document order, including
+    /// nodes inside closed ones.
+    ///
+    /// `scope` limits the replacement to a node and its descendants; None
+    /// covers the whole forest. An unknown scope changes nothing.
+    pub fn preview<T>(&self, roots: &[Node<T>], scope: Option<NodeId>) -> Vec<Rename> {
+        let nodes = match scope {
+            Some(id) => match tree::find(roots, id) {
+                Some(node) => document_order(slice::from_ref(node)),
+                None => return Vec::new(),
+            },
+            None => document_order(roots),
+        };
+
+        nodes
+            .into_iter()
+            .filter_map(|node| {
+                let to = self.apply(&node.label)?;
+                Some(Rename {
+                    id: node.id(),
+                    from: node.label.clone(),
+                    to,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_literal_takes_replacement_as_is() {
+        let replace = Replace::literal("a.b", "$1");
+
+        assert_eq!(replace.apply("a.b and a.b"), Some("$1 and $1".into()));
+        assert_eq!(replace.apply("axb"), None);
+        assert_eq!(Replace::literal("", "x").apply("label"), None);
+    }
+
+    #[test]
+    fn test_regex_expands_capture_groups() {
+        let replace = Replace::regex(r"(\w+) (\d+)", "${2}: $1").unwrap();
+
+        assert_eq!(replace.apply("Draft 12"), Some("12: Draft".into()));
+        assert!(Replace::regex("(", "").is_err());
+    }
+
+    #[test]
+    fn test_preview_whole_forest_or_subtree() {
+        let roots = vec![
+            Node::new(
+                "Draft 1",
+                false,
+                vec![
+                    Node::new("Draft 1.1", false, vec![]),
+                    Node::new("Final", false, vec![]),
+                ],
+            ),
+            Node::new("Draft 2", false, vec![]),
+        ];
+        let replace = Replace::literal("Draft", "Version");
+
+        let all = replace.preview(&roots, None);
+        assert_eq!(
+            all.iter()
+                .map(|rename| rename.to.as_str())
+                .collect::<Vec<_>>(),
+            ["Version 1", "Version 1.1", "Version 2"]
+        );
+        assert_eq!(all[1].id, roots[0].children[0].id());
+        assert_eq!(all[1].from, "Draft 1.1");
+
+        let subtree = replace.preview(&roots, Some(roots[0].children[0].id()));
+        assert_eq!(subtree.len(), 1);
+        let other = Node::new("Draft elsewhere", false, vec![]);
+        assert!(replace.preview(&roots, Some(other.id())).is_empty());
+    }
+}
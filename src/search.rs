@@ -0,0 +1,217 @@
+use std::fmt;
+
+use regex::Regex;
+
+use crate::tree::{Node, NodeId};
+
+/// How a query is compared against labels
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// The label contains the query exactly
+    Substring,
+    /// The label contains the query, ignoring case
+    #[default]
+    IgnoreCase,
+    /// The query's characters appear in the label in order, possibly with
+    /// others in between, ignoring case
+    Fuzzy,
+    /// The label matches the query as a regular expression
+    Regex,
+}
+
+impl Mode {
+    pub const ALL: [Mode; 4] = [Mode::Substring, Mode::IgnoreCase, Mode::Fuzzy, Mode::Regex];
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Mode::Substring => "Match case",
+            Mode::IgnoreCase => "Ignore case",
+            Mode::Fuzzy => "Fuzzy",
+            Mode::Regex => "Regex",
+        })
+    }
+}
+
+/// Which way [`Search::next`] steps through the matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Forward,
+    Backward,
+}
+
+/// Finds nodes by label anywhere in a forest, including inside closed
+/// nodes, in document order: each node comes before its children, and
+/// children before the node's next sibling.
+#[derive(Debug, Clone)]
+pub struct Search {
+    query: String,
+    mode: Mode,
+    regex: Option<Regex>,
+}
+
+impl Search {
+    /// Fails when `mode` is [`Mode::Regex`] and the query is not a valid
+    /// regular expression
+    pub fn new(query: impl Into<String>, mode: Mode) -> Result<Self, regex::Error> {
+        let query = query.into();
+        let regex = match mode {
+            Mode::Regex => Some(Regex::new(&query)?),
+            Mode::Substring | Mode::IgnoreCase | Mode::Fuzzy => None,
+        };
+        Ok(Self { query, mode, regex })
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// An empty query matches nothing
+    pub fn is_empty(&self) -> bool {
+        self.query.is_empty()
+    }
+
+    pub fn matches(&self, label: &str) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+
+        match self.mode {
+            Mode::Substring => label.contains(&self.query),
+            Mode::IgnoreCase => label.to_lowercase().contains(&self.query.to_lowercase()),
+            Mode::Fuzzy => {
+                let mut label = label.chars().flat_map(char::to_lowercase);
+                self.query
+                    .chars()
+                    .flat_map(char::to_lowercase)
+                    .all(|wanted| label.any(|c| c == wanted))
+            }
+            Mode::Regex => self
+                .regex
+                .as_ref()
+                .is_some_and(|regex| regex.is_match(label)),
+        }
+    }
+
+    /// Ids of all matching nodes, in document order
+    pub fn matches_in<T>(&self, roots: &[Node<T>]) -> Vec<NodeId> {
+        document_order(roots)
+            .into_iter()
+            .filter(|node| self.matches(&node.label))
+            .map(Node::id)
+            .collect()
+    }
+
+    /// The first match after `from` in document order, or before it when
+    /// searching backward, wrapping around at the ends.
+    ///
+    /// Without `from`, or when it is not in the forest, the search starts
+    /// at the first (or last) node. Returns `from` itself when it is the
+    /// only match, and None when nothing matches.
+    pub fn next<T>(
+        &self,
+        roots: &[Node<T>],
+        from: Option<NodeId>,
+        direction: Direction,
+    ) -> Option<NodeId> {
+        let nodes = document_order(roots);
+        let len = nodes.len();
+        let position = from.and_then(|id| nodes.iter().position(|node| node.id() == id));
+
+        (1..=len)
+            .map(|step| match direction {
+                Direction::Forward => (position.unwrap_or(len - 1) + step) % len,
+                Direction::Backward => (position.unwrap_or(0) + len - step) % len,
+            })
+            .map(|index| nodes[index])
+            .find(|node| self.matches(&node.label))
+            .map(Node::id)
+    }
+}
+
+/// Every node of the forest, each before its children
+pub(crate) fn document_order<T>(roots: &[Node<T>]) -> Vec<&Node<T>> {
+    fn walk<'a, T>(nodes: &'a [Node<T>], order: &mut Vec<&'a Node<T>>) {
+        for node in nodes {
+            order.push(node);
+            walk(&node.children, order);
+        }
+    }
+
+    let mut order = Vec::new();
+    walk(roots, &mut order);
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::tests::sample;
+
+    fn search(query: &str, mode: Mode) -> Search {
+        Search::new(query, mode).unwrap()
+    }
+
+    #[test]
+    fn test_modes() {
+        assert!(search("Tomato", Mode::Substring).matches("Plant Tomatoes"));
+        assert!(!search("Tomato", Mode::Substring).matches("tomato soup"));
+        assert!(search("TOMATO", Mode::IgnoreCase).matches("tomato soup"));
+        assert!(search("pltm", Mode::Fuzzy).matches("Plant Tomatoes"));
+        assert!(!search("mtp", Mode::Fuzzy).matches("Plant Tomatoes"));
+        assert!(search(r"\d{4}$", Mode::Regex).matches("Taxes 2024"));
+        assert!(!search("", Mode::IgnoreCase).matches("anything"));
+        assert!(Search::new("(unclosed", Mode::Regex).is_err());
+    }
+
+    #[test]
+    fn test_matches_in_closed_subtrees_in_document_order() {
+        let roots = sample();
+
+        let matches = search("E", Mode::IgnoreCase).matches_in(&roots);
+
+        // Leaf is inside the closed Branch
+        assert_eq!(
+            matches,
+            [
+                roots[0].children[0].children[0].id(),
+                roots[0].children[1].id(),
+                roots[1].id()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_next_and_previous_wrap_around() {
+        let roots = sample();
+        let search = search("E", Mode::IgnoreCase);
+        let leaf = roots[0].children[0].children[0].id();
+        let second = roots[1].id();
+
+        assert_eq!(search.next(&roots, None, Direction::Forward), Some(leaf));
+        assert_eq!(search.next(&roots, None, Direction::Backward), Some(second));
+        assert_eq!(
+            search.next(&roots, Some(roots[0].children[0].id()), Direction::Forward),
+            Some(leaf)
+        );
+        assert_eq!(
+            search.next(&roots, Some(second), Direction::Forward),
+            Some(leaf)
+        );
+        assert_eq!(
+            search.next(&roots, Some(leaf), Direction::Backward),
+            Some(second)
+        );
+        assert_eq!(
+            Search::new("nothing", Mode::Fuzzy)
+                .unwrap()
+                .next(&roots, None, Direction::Forward),
+            None
+        );
+    }
+}
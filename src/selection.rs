@@ -0,0 +1,276 @@
+use std::collections::HashSet;
+
+use iced::keyboard::Modifiers;
+
+use crate::tree::{self, Node, NodeId, VisibleRow};
+
+/// How a click on a row changes the selection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectMode {
+    /// Select only the clicked node
+    Replace,
+    /// Add or remove the clicked node, keeping the rest (Ctrl-click)
+    Toggle,
+    /// Select the visible range between the anchor and the clicked node (Shift-click)
+    Extend,
+}
+
+impl SelectMode {
+    /// Picks the mode matching the keyboard modifiers held during a click
+    pub fn from_modifiers(modifiers: Modifiers) -> Self {
+        if modifiers.shift() {
+            Self::Extend
+        } else if modifiers.command() {
+            Self::Toggle
+        } else {
+            Self::Replace
+        }
+    }
+}
+
+/// Set of selected nodes plus the anchor used for range selection
+#[derive(Debug, Clone, Default)]
+pub struct Selection {
+    selected: HashSet<NodeId>,
+    anchor: Option<NodeId>,
+}
+
+impl Selection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_selected(&self, id: NodeId) -> bool {
+        self.selected.contains(&id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.selected.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.selected.is_empty()
+    }
+
+    /// Selected nodes in tree order, leaving out those that lie inside
+    /// another selected node
+    pub fn topmost<T>(&self, roots: &[Node<T>]) -> Vec<NodeId> {
+        fn walk<T>(nodes: &[Node<T>], selection: &Selection, ids: &mut Vec<NodeId>) {
+            for node in nodes {
+                if selection.is_selected(node.id()) {
+                    ids.push(node.id());
+                } else {
+                    walk(&node.children, selection, ids);
+                }
+            }
+        }
+
+        let mut ids = Vec::new();
+        walk(roots, self, &mut ids);
+        ids
+    }
+
+    /// Iterates over the selected ids in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.selected.iter().copied()
+    }
+
+    /// The node range selection extends from, usually the last clicked one
+    pub fn anchor(&self) -> Option<NodeId> {
+        self.anchor
+    }
+
+    /// Selects only the given node
+    pub fn select(&mut self, id: NodeId) {
+        self.selected.clear();
+        self.selected.insert(id);
+        self.anchor = Some(id);
+    }
+
+    /// Adds the node to the selection, or removes it if already selected
+    pub fn toggle(&mut self, id: NodeId) {
+        if !self.selected.remove(&id) {
+            self.selected.insert(id);
+        }
+        self.anchor = Some(id);
+    }
+
+    /// Selects every visible row between the anchor and the given node.
+    /// Falls back to a plain selection when the anchor is not visible.
+    pub fn extend<T>(&mut self, roots: &[Node<T>], id: NodeId) {
+        self.extend_rows(&tree::visible_rows(roots), id);
+    }
+
+    /// Like [`Selection::extend`], over rows that were already laid out
+    pub fn extend_rows<T>(&mut self, rows: &[VisibleRow<'_, T>], id: NodeId) {
+        let position = |id| rows.iter().position(|row| row.node.id() == id);
+
+        let Some((from, to)) = self.anchor.and_then(position).zip(position(id)) else {
+            self.select(id);
+            return;
+        };
+
+        self.selected.clear();
+        let range = from.min(to)..=from.max(to);
+        self.selected
+            .extend(rows[range].iter().map(|row| row.node.id()));
+    }
+
+    /// Updates the selection for a click on `id` with the given mode
+    pub fn apply<T>(&mut self, roots: &[Node<T>], id: NodeId, mode: SelectMode) {
+        self.apply_rows(&tree::visible_rows(roots), id, mode);
+    }
+
+    /// Like [`Selection::apply`], with ranges taken from the given rows
+    pub fn apply_rows<T>(&mut self, rows: &[VisibleRow<'_, T>], id: NodeId, mode: SelectMode) {
+        match mode {
+            SelectMode::Replace => self.select(id),
+            SelectMode::Toggle => self.toggle(id),
+            SelectMode::Extend => self.extend_rows(rows, id),
+        }
+    }
+
+    /// Selects every visible row
+    pub fn select_all<T>(&mut self, roots: &[Node<T>]) {
+        self.select_all_rows(&tree::visible_rows(roots));
+    }
+
+    /// Selects every one of the given rows
+    pub fn select_all_rows<T>(&mut self, rows: &[VisibleRow<'_, T>]) {
+        self.selected = rows.iter().map(|row| row.node.id()).collect();
+    }
+
+    pub fn clear(&mut self) {
+        self.selected.clear();
+        self.anchor = None;
+    }
+
+    /// Drops ids that no longer exist in the forest, e.g. after a removal
+    pub fn retain_existing<T>(&mut self, roots: &[Node<T>]) {
+        self.selected.retain(|&id| tree::find(roots, id).is_some());
+        if self
+            .anchor
+            .is_some_and(|id| tree::find(roots, id).is_none())
+        {
+            self.anchor = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::tests::sample;
+
+    fn id(roots: &[Node], path: &[usize]) -> NodeId {
+        tree::get(roots, path).unwrap().id()
+    }
+
+    #[test]
+    fn test_mode_from_modifiers() {
+        assert_eq!(
+            SelectMode::from_modifiers(Modifiers::empty()),
+            SelectMode::Replace
+        );
+        assert_eq!(
+            SelectMode::from_modifiers(Modifiers::SHIFT),
+            SelectMode::Extend
+        );
+        assert_eq!(
+            SelectMode::from_modifiers(Modifiers::COMMAND),
+            SelectMode::Toggle
+        );
+    }
+
+    #[test]
+    fn test_select_replaces() {
+        let roots = sample();
+        let mut selection = Selection::new();
+
+        selection.select(id(&roots, &[0]));
+        selection.select(id(&roots, &[1]));
+
+        assert_eq!(selection.len(), 1);
+        assert!(selection.is_selected(id(&roots, &[1])));
+        assert_eq!(selection.anchor(), Some(id(&roots, &[1])));
+    }
+
+    #[test]
+    fn test_toggle_adds_and_removes() {
+        let roots = sample();
+        let mut selection = Selection::new();
+
+        selection.toggle(id(&roots, &[0]));
+        selection.toggle(id(&roots, &[1]));
+        assert_eq!(selection.len(), 2);
+
+        selection.toggle(id(&roots, &[0]));
+        assert_eq!(selection.len(), 1);
+        assert!(!selection.is_selected(id(&roots, &[0])));
+    }
+
+    #[test]
+    fn test_extend_selects_visible_range() {
+        let roots = sample();
+        let mut selection = Selection::new();
+
+        selection.select(id(&roots, &[1]));
+        selection.apply(&roots, id(&roots, &[0, 0]), SelectMode::Extend);
+
+        // Branch, Other and Second; the hidden leaf is skipped
+        assert_eq!(selection.len(), 3);
+        assert!(selection.is_selected(id(&roots, &[0, 1])));
+        assert!(!selection.is_selected(id(&roots, &[0, 0, 0])));
+        assert_eq!(selection.anchor(), Some(id(&roots, &[1])));
+    }
+
+    #[test]
+    fn test_extend_without_anchor_selects_single() {
+        let roots = sample();
+        let mut selection = Selection::new();
+
+        selection.extend(&roots, id(&roots, &[0, 1]));
+
+        assert_eq!(selection.len(), 1);
+        assert_eq!(selection.anchor(), Some(id(&roots, &[0, 1])));
+    }
+
+    #[test]
+    fn test_select_all_visible() {
+        let roots = sample();
+        let mut selection = Selection::new();
+
+        selection.select_all(&roots);
+
+        assert_eq!(selection.len(), 4);
+    }
+
+    #[test]
+    fn test_topmost_skips_nested_selection() {
+        let roots = sample();
+        let mut selection = Selection::new();
+        selection.select(id(&roots, &[1]));
+        selection.toggle(id(&roots, &[0, 0, 0]));
+        selection.toggle(id(&roots, &[0, 0]));
+
+        assert_eq!(
+            selection.topmost(&roots),
+            [id(&roots, &[0, 0]), id(&roots, &[1])]
+        );
+    }
+
+    #[test]
+    fn test_retain_existing_after_removal() {
+        let mut roots = sample();
+        let mut selection = Selection::new();
+        selection.select(id(&roots, &[0, 0]));
+        selection.toggle(id(&roots, &[1]));
+
+        let removed = roots[0].remove_child(0).unwrap();
+        selection.retain_existing(&roots);
+
+        assert!(!selection.is_selected(removed.id()));
+        assert_eq!(selection.len(), 1);
+        assert_eq!(selection.anchor(), Some(id(&roots, &[1])));
+    }
+}
@@ -0,0 +1,271 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::history::Edit;
+use crate::tree::{self, Node, NodeId};
+
+/// How labels are compared when sorting nodes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Order {
+    /// By character code, so uppercase letters come before lowercase ones
+    Alphabetical,
+    /// Alphabetically, ignoring case
+    #[default]
+    IgnoreCase,
+    /// Ignoring case, with runs of digits compared by their value, so
+    /// "Leaf 2" comes before "Leaf 10"
+    Natural,
+}
+
+impl Order {
+    pub const ALL: [Order; 3] = [Order::Alphabetical, Order::IgnoreCase, Order::Natural];
+
+    pub fn compare(self, a: &str, b: &str) -> Ordering {
+        match self {
+            Order::Alphabetical => a.cmp(b),
+            Order::IgnoreCase => ignore_case(a, b).then_with(|| a.cmp(b)),
+            Order::Natural => natural(a, b),
+        }
+    }
+
+    /// Comparator of nodes by label, for [`sort`], [`edits`] and
+    /// [`position`]
+    pub fn by_label<T>(self) -> impl Fn(&Node<T>, &Node<T>) -> Ordering {
+        move |a, b| self.compare(&a.label, &b.label)
+    }
+}
+
+impl fmt::Display for Order {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Order::Alphabetical => "A-Z",
+            Order::IgnoreCase => "A-Z, ignoring case",
+            Order::Natural => "Natural",
+        })
+    }
+}
+
+/// Compares labels in natural order: digit runs by value, everything else
+/// ignoring case. Labels that only differ in case or leading zeros fall
+/// back to comparing by character code.
+pub fn natural(a: &str, b: &str) -> Ordering {
+    let (mut left, mut right) = (chunks(a), chunks(b));
+    loop {
+        let ordering = match (left.next(), right.next()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if is_number(x) && is_number(y) => {
+                let (x, y) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+                x.len().cmp(&y.len()).then_with(|| x.cmp(y))
+            }
+            (Some(x), Some(y)) => ignore_case(x, y),
+        };
+        if ordering.is_ne() {
+            return ordering;
+        }
+    }
+}
+
+/// Sorts a list of siblings in place, and with `recursive` the children of
+/// every node below them too. Nodes that compare equal keep their order.
+pub fn sort<T>(
+    nodes: &mut [Node<T>],
+    recursive: bool,
+    compare: &impl Fn(&Node<T>, &Node<T>) -> Ordering,
+) {
+    nodes.sort_by(compare);
+    if recursive {
+        for node in nodes {
+            sort(&mut node.children, true, compare);
+        }
+    }
+}
+
+/// The moves that sort the children of `parent`, or the roots when it is
+/// None, for applying through the history as one transaction. With
+/// `recursive` the children of every node below are sorted as well. Nodes
+/// already in place are not moved, so a sorted forest gives no edits.
+pub fn edits<T>(
+    roots: &[Node<T>],
+    parent: Option<NodeId>,
+    recursive: bool,
+    compare: &impl Fn(&Node<T>, &Node<T>) -> Ordering,
+) -> Vec<Edit<T>> {
+    fn collect<T>(
+        siblings: &[Node<T>],
+        parent: Option<NodeId>,
+        recursive: bool,
+        compare: &impl Fn(&Node<T>, &Node<T>) -> Ordering,
+        edits: &mut Vec<Edit<T>>,
+    ) {
+        let mut sorted: Vec<_> = siblings.iter().collect();
+        sorted.sort_by(|a, b| compare(a, b));
+
+        // Moving each node to its index in turn leaves the ones before it
+        // in place
+        let mut current: Vec<_> = siblings.iter().map(Node::id).collect();
+        for (index, node) in sorted.iter().enumerate() {
+            let id = node.id();
+            if current[index] == id {
+                continue;
+            }
+            current.retain(|&other| other != id);
+            current.insert(index, id);
+            edits.push(Edit::Move { id, parent, index });
+        }
+
+        if recursive {
+            for node in siblings {
+                collect(&node.children, Some(node.id()), true, compare, edits);
+            }
+        }
+    }
+
+    let siblings = match parent {
+        Some(id) => match tree::find(roots, id) {
+            Some(node) => &node.children,
+            None => return Vec::new(),
+        },
+        None => roots,
+    };
+    let mut edits = Vec::new();
+    collect(siblings, parent, recursive, compare, &mut edits);
+    edits
+}
+
+/// Index at which `node` belongs among sorted `siblings`: after every
+/// sibling that does not compare greater. `node` itself is skipped if it
+/// is one of them, so the index suits both [`Edit::Insert`] for a new node
+/// and [`Edit::Move`] for a renamed one.
+pub fn position<T>(
+    siblings: &[Node<T>],
+    node: &Node<T>,
+    compare: &impl Fn(&Node<T>, &Node<T>) -> Ordering,
+) -> usize {
+    siblings
+        .iter()
+        .filter(|sibling| sibling.id() != node.id())
+        .take_while(|sibling| compare(sibling, node).is_le())
+        .count()
+}
+
+fn ignore_case(a: &str, b: &str) -> Ordering {
+    a.chars()
+        .flat_map(char::to_lowercase)
+        .cmp(b.chars().flat_map(char::to_lowercase))
+}
+
+fn is_number(chunk: &str) -> bool {
+    chunk.starts_with(|c: char| c.is_ascii_digit())
+}
+
+/// Splits text into alternating runs of ASCII digits and other characters
+fn chunks(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        let digits = rest.chars().next()?.is_ascii_digit();
+        let end = rest
+            .find(|c: char| c.is_ascii_digit() != digits)
+            .unwrap_or(rest.len());
+        let (chunk, tail) = rest.split_at(end);
+        rest = tail;
+        Some(chunk)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::tests::labels;
+
+    fn sample() -> Vec<Node> {
+        vec![
+            Node::new(
+                "beta",
+                false,
+                vec![
+                    Node::new("Leaf 10", false, vec![]),
+                    Node::new("Leaf 2", false, vec![]),
+                    Node::new("leaf 1", false, vec![]),
+                ],
+            ),
+            Node::new("Alpha", false, vec![]),
+        ]
+    }
+
+    fn sorted(order: Order, labels: &[&str]) -> Vec<String> {
+        let mut labels: Vec<_> = labels.iter().map(|label| label.to_string()).collect();
+        labels.sort_by(|a, b| order.compare(a, b));
+        labels
+    }
+
+    #[test]
+    fn test_orders() {
+        let labels = ["Leaf 10", "leaf 1", "Leaf 2", "Leaf 02"];
+
+        assert_eq!(
+            sorted(Order::Alphabetical, &labels),
+            ["Leaf 02", "Leaf 10", "Leaf 2", "leaf 1"]
+        );
+        assert_eq!(
+            sorted(Order::IgnoreCase, &labels),
+            ["Leaf 02", "leaf 1", "Leaf 10", "Leaf 2"]
+        );
+        assert_eq!(
+            sorted(Order::Natural, &labels),
+            ["leaf 1", "Leaf 02", "Leaf 2", "Leaf 10"]
+        );
+        assert_eq!(natural("v2", "v2.1"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_sort_direct_or_recursive() {
+        let mut roots = sample();
+        sort(&mut roots, false, &Order::Natural.by_label());
+        assert_eq!(labels(&roots), ["Alpha", "beta"]);
+        assert_eq!(labels(&roots[1].children), ["Leaf 10", "Leaf 2", "leaf 1"]);
+
+        sort(&mut roots, true, &Order::Natural.by_label());
+        assert_eq!(labels(&roots[1].children), ["leaf 1", "Leaf 2", "Leaf 10"]);
+    }
+
+    #[test]
+    fn test_sort_with_custom_comparator() {
+        let mut roots = sample();
+
+        sort(&mut roots, false, &|a: &Node, b: &Node| {
+            b.children.len().cmp(&a.children.len())
+        });
+
+        assert_eq!(labels(&roots), ["beta", "Alpha"]);
+    }
+
+    #[test]
+    fn test_edits_sort_through_history() {
+        let mut roots = sample();
+        let edits = edits(&roots, None, true, &Order::Natural.by_label());
+        assert_eq!(edits.len(), 3);
+
+        for edit in edits {
+            edit.apply(&mut roots).unwrap();
+        }
+        assert_eq!(labels(&roots), ["Alpha", "beta"]);
+        assert_eq!(labels(&roots[1].children), ["leaf 1", "Leaf 2", "Leaf 10"]);
+        assert!(super::edits(&roots, None, true, &Order::Natural.by_label()).is_empty());
+    }
+
+    #[test]
+    fn test_position_skips_the_node_itself() {
+        let mut roots = sample();
+        sort(&mut roots, true, &Order::Natural.by_label());
+        let compare = Order::Natural.by_label();
+
+        let new = Node::new("Leaf 3", false, vec![]);
+        assert_eq!(position(&roots[1].children, &new, &compare), 2);
+
+        roots[1].children[0].label = "Leaf 99".into();
+        let children = &roots[1].children;
+        assert_eq!(position(children, &children[0], &compare), 2);
+    }
+}
@@ -1,30 +1,169 @@
-use crate::{Column, Message, button, column, text, text_input};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
 
-/// Node state: open/closed, label, children
-#[derive(Debug, Clone)]
-pub struct Node {
+use serde::{Deserialize, Serialize};
+
+/// Stable identifier of a node, independent of its position in the tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(u64);
+
+impl NodeId {
+    /// Allocates an identifier that has not been handed out before
+    fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// Whether a node's children are in memory, for trees that read them on
+/// demand
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Load {
+    /// `children` holds all of the node's children
+    #[default]
+    Loaded,
+    /// The node may have children that have not been read yet
+    Unloaded,
+    /// The children are being read
+    Loading,
+    /// Reading the children failed for the given reason; can be retried
+    Failed(String),
+}
+
+impl Load {
+    pub fn is_loaded(&self) -> bool {
+        *self == Load::Loaded
+    }
+
+    /// Whether reading the children can be started
+    pub fn can_start(&self) -> bool {
+        matches!(self, Load::Unloaded | Load::Failed(_))
+    }
+}
+
+/// Node state: open/closed, label, children and an application payload
+///
+/// Serializes without its id; deserialized nodes get fresh ids. Missing
+/// `open`, `children`, `load` and `data` fields fall back to their
+/// defaults. A node that is loading or failed to load is stored as unloaded.
+///
+/// Cloning a node keeps its id and those of its descendants, so a clone
+/// stands for the same nodes, e.g. in a recorded [`Edit`](crate::Edit). Use
+/// [`Node::duplicate`] for a copy that can sit beside the original.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Node<T = ()> {
+    #[serde(skip, default = "NodeId::next")]
+    id: NodeId,
     pub label: String,
+    #[serde(default)]
     pub open: bool,
-    pub children: Vec<Node>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Node<T>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Load::is_loaded",
+        serialize_with = "serialize_load"
+    )]
+    pub load: Load,
+    #[serde(default)]
+    pub data: T,
+}
+
+/// Stores every state but `Loaded` as `Unloaded`, the only one that makes
+/// sense in a new session
+fn serialize_load<S: serde::Serializer>(load: &Load, serializer: S) -> Result<S::Ok, S::Error> {
+    match load {
+        Load::Loaded => Load::Loaded.serialize(serializer),
+        _ => Load::Unloaded.serialize(serializer),
+    }
 }
 
 impl Node {
     pub fn new(label: &str, open: bool, children: Vec<Node>) -> Self {
+        Self::with_data(label, (), open, children)
+    }
+}
+
+impl<T: fmt::Display> Node<T> {
+    /// Creates a node whose label is the payload's `Display` output
+    pub fn from_data(data: T, open: bool, children: Vec<Node<T>>) -> Self {
+        let label = data.to_string();
+        Self::with_data(&label, data, open, children)
+    }
+}
+
+impl<T: Clone> Node<T> {
+    /// Deep copy of the subtree in which every node gets a fresh id. A copy
+    /// of a node that is still loading is left unloaded.
+    pub fn duplicate(&self) -> Self {
+        let mut copy = Self::with_data(
+            &self.label,
+            self.data.clone(),
+            self.open,
+            self.children.iter().map(Node::duplicate).collect(),
+        );
+        copy.load = match &self.load {
+            Load::Loading => Load::Unloaded,
+            load => load.clone(),
+        };
+        copy
+    }
+}
+
+impl<T> Node<T> {
+    /// Creates a node carrying a payload beside its label
+    pub fn with_data(label: &str, data: T, open: bool, children: Vec<Node<T>>) -> Self {
+        Self::with_id(NodeId::next(), label, data, open, children)
+    }
+
+    /// Recreates a node under an id that was allocated earlier
+    pub(crate) fn with_id(
+        id: NodeId,
+        label: &str,
+        data: T,
+        open: bool,
+        children: Vec<Node<T>>,
+    ) -> Self {
         Self {
+            id,
             label: label.into(),
             open,
             children,
+            load: Load::Loaded,
+            data,
+        }
+    }
+
+    /// Returns the identifier allocated to this node when it was created
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    /// Finds the node with the given id in this subtree
+    pub fn find(&self, id: NodeId) -> Option<&Node<T>> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.children.iter().find_map(|child| child.find(id))
+    }
+
+    /// Finds the node with the given id in this subtree, mutably
+    pub fn find_mut(&mut self, id: NodeId) -> Option<&mut Node<T>> {
+        if self.id == id {
+            return Some(self);
         }
+        self.children.iter_mut().find_map(|child| child.find_mut(id))
     }
 
     /// Adds a child node to the end of the children list
-    pub fn add_child(&mut self, child: Node) {
+    pub fn add_child(&mut self, child: Node<T>) {
         self.children.push(child);
     }
 
     /// Removes a child at the specified index
     /// Returns the removed node if successful, None if index is out of bounds
-    pub fn remove_child(&mut self, index: usize) -> Option<Node> {
+    pub fn remove_child(&mut self, index: usize) -> Option<Node<T>> {
         if index < self.children.len() {
             Some(self.children.remove(index))
         } else {
@@ -32,9 +171,35 @@ impl Node {
         }
     }
 
+    /// Whether the node has children or may have some that are not loaded,
+    /// i.e. whether it can be opened
+    pub fn is_expandable(&self) -> bool {
+        !self.children.is_empty() || !self.load.is_loaded()
+    }
+
+    /// Counts every node below this one, at any depth
+    pub fn descendant_count(&self) -> usize {
+        self.children
+            .iter()
+            .map(|child| 1 + child.descendant_count())
+            .sum()
+    }
+
+    /// Marks every node of the subtree that is still loading as unloaded,
+    /// for a subtree detached while its reads were in flight: their results
+    /// are dropped, so the reads have to be started again
+    pub fn cancel_loads(&mut self) {
+        if self.load == Load::Loading {
+            self.load = Load::Unloaded;
+        }
+        for child in &mut self.children {
+            child.cancel_loads();
+        }
+    }
+
     /// Inserts a child at the specified index
     /// If index is greater than the length, the child is added at the end
-    pub fn insert_child(&mut self, index: usize, child: Node) {
+    pub fn insert_child(&mut self, index: usize, child: Node<T>) {
         if index >= self.children.len() {
             self.children.push(child);
         } else {
@@ -43,73 +208,250 @@ impl Node {
     }
 }
 
-/// Render a tree node and its children using standard widgets
-pub fn tree_view<'a>(
-    node: &'a Node,
-    path: Vec<usize>,
-    editing_path: &'a Option<Vec<usize>>,
-    edit_value: &'a str,
-) -> Column<'a, Message> {
-    let indent = path.len() as f32 * 24.0;
-    
-    let icon = if node.children.is_empty() {
-        "  "
-    } else if node.open {
-        "▼ "
+/// Gets a reference to the node at the specified path in a forest.
+/// The first element of the path is the root index, subsequent elements navigate through children.
+/// Returns None if the path is empty or if any index is out of bounds.
+pub fn get<'a, T>(roots: &'a [Node<T>], path: &[usize]) -> Option<&'a Node<T>> {
+    let (first, rest) = path.split_first()?;
+    let mut node = roots.get(*first)?;
+    for i in rest {
+        node = node.children.get(*i)?;
+    }
+    Some(node)
+}
+
+/// Gets a mutable reference to the node at the specified path in a forest.
+/// Returns None if the path is empty or if any index is out of bounds.
+pub fn get_mut<'a, T>(roots: &'a mut [Node<T>], path: &[usize]) -> Option<&'a mut Node<T>> {
+    let (first, rest) = path.split_first()?;
+    let mut node = roots.get_mut(*first)?;
+    for i in rest {
+        node = node.children.get_mut(*i)?;
+    }
+    Some(node)
+}
+
+/// Finds the node with the given id anywhere in a forest
+pub fn find<T>(roots: &[Node<T>], id: NodeId) -> Option<&Node<T>> {
+    roots.iter().find_map(|root| root.find(id))
+}
+
+/// Finds the node with the given id anywhere in a forest, mutably
+pub fn find_mut<T>(roots: &mut [Node<T>], id: NodeId) -> Option<&mut Node<T>> {
+    roots.iter_mut().find_map(|root| root.find_mut(id))
+}
+
+/// Resolves a node id to its current index path in a forest
+pub fn path_of<T>(roots: &[Node<T>], id: NodeId) -> Option<Vec<usize>> {
+    fn walk<T>(nodes: &[Node<T>], id: NodeId, path: &mut Vec<usize>) -> bool {
+        for (i, node) in nodes.iter().enumerate() {
+            path.push(i);
+            if node.id == id || walk(&node.children, id, path) {
+                return true;
+            }
+            path.pop();
+        }
+        false
+    }
+
+    let mut path = Vec::new();
+    walk(roots, id, &mut path).then_some(path)
+}
+
+/// Paths of all nodes in a forest by id, so that looking a node up does not
+/// walk the tree.
+///
+/// The index is a snapshot: it is not updated when the forest changes.
+/// [`Index::path`] checks the recorded path against the forest and returns
+/// None when it no longer leads to the node, telling the caller to rebuild.
+#[derive(Debug, Clone, Default)]
+pub struct Index {
+    paths: HashMap<NodeId, Vec<usize>>,
+}
+
+impl Index {
+    pub fn new<T>(roots: &[Node<T>]) -> Self {
+        let mut paths = HashMap::new();
+        let mut stack: Vec<_> = roots
+            .iter()
+            .enumerate()
+            .map(|(i, root)| (root, vec![i]))
+            .collect();
+        while let Some((node, path)) = stack.pop() {
+            for (i, child) in node.children.iter().enumerate() {
+                let mut child_path = path.clone();
+                child_path.push(i);
+                stack.push((child, child_path));
+            }
+            paths.insert(node.id, path);
+        }
+        Self { paths }
+    }
+
+    /// The node's path, if the forest has not moved it since the index was
+    /// built
+    pub fn path<T>(&self, roots: &[Node<T>], id: NodeId) -> Option<&[usize]> {
+        let path = self.paths.get(&id)?;
+        (get(roots, path)?.id == id).then_some(path.as_slice())
+    }
+}
+
+/// Detaches a node with its subtree and reinserts it at `index` among the
+/// children of `to_parent`, or among the roots when that is None. The index
+/// counts positions after the node has been detached; past the end appends.
+///
+/// Returns the node's previous parent and index, or None without touching
+/// the forest if either node is missing or `to_parent` lies in the moved
+/// subtree.
+pub fn move_node<T>(
+    roots: &mut Vec<Node<T>>,
+    id: NodeId,
+    to_parent: Option<NodeId>,
+    index: usize,
+) -> Option<(Option<NodeId>, usize)> {
+    if let Some(to_parent) = to_parent
+        && (find(roots, id)?.find(to_parent).is_some() || find(roots, to_parent).is_none())
+    {
+        return None;
+    }
+
+    let path = path_of(roots, id)?;
+    let (&from_index, parent_path) = path.split_last()?;
+    let (from_parent, node) = if parent_path.is_empty() {
+        (None, roots.remove(from_index))
     } else {
-        "▶ "
+        let parent = get_mut(roots, parent_path)?;
+        (Some(parent.id), parent.children.remove(from_index))
     };
-    
-    let is_editing = editing_path.as_ref() == Some(&path);
-    
-    let node_widget = if is_editing {
-        // Show text input when editing
-        column![
-            text_input(&format!("Edit {}", node.label), edit_value)
-                .on_input(Message::EditLabel)
-                .on_submit(Message::FinishEdit)
-                .padding(2)
-        ]
-    } else {
-        // Show clickable label when not editing        
-        let toggle_button = button(text(format!("{}{}", icon, node.label)))
-            .on_press(Message::Toggle(path.clone()))
-            .style(iced::widget::button::text)
-            .padding(0);
-        
-        const EDIT_BUTTON_LEFT_PADDING: f32 = 8.0;
-        let edit_button = button(text("✏"))
-            .on_press(Message::StartEdit(path.clone()))
-            .style(iced::widget::button::text)
-            .padding(iced::Padding::new(0.0).left(EDIT_BUTTON_LEFT_PADDING));
-        
-        column![
-            iced::widget::row![toggle_button, edit_button]
-                .spacing(4)
-        ]
+
+    let siblings = match to_parent {
+        Some(to_parent) => &mut find_mut(roots, to_parent)?.children,
+        None => roots,
     };
-    
-    let node_row = node_widget
-        .padding(iced::Padding::new(0.0).left(indent));
-    
-    let mut col = column![node_row];
-    
-    // Recursively render children if the node is open
-    if node.open {
-        for (i, child) in node.children.iter().enumerate() {
-            let mut child_path = path.clone();
-            child_path.push(i);
-            col = col.push(tree_view(child, child_path, editing_path, edit_value));
+    siblings.insert(index.min(siblings.len()), node);
+    Some((from_parent, from_index))
+}
+
+/// Where a dragged node lands relative to the row it is dropped on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropPosition {
+    /// Sibling placed just above the target
+    Before,
+    /// Sibling placed just below the target
+    After,
+    /// Last child of the target
+    Inside,
+}
+
+/// Works out the `(parent, index)` arguments of [`move_node`] for dropping
+/// `id` on `target`. Returns None when the drop would put the node inside its
+/// own subtree.
+pub fn drop_destination<T>(
+    roots: &[Node<T>],
+    id: NodeId,
+    target: NodeId,
+    position: DropPosition,
+) -> Option<(Option<NodeId>, usize)> {
+    if find(roots, id)?.find(target).is_some() {
+        return None;
+    }
+
+    let target_path = path_of(roots, target)?;
+    if position == DropPosition::Inside {
+        return Some((Some(target), usize::MAX));
+    }
+
+    let (&target_index, parent_path) = target_path.split_last()?;
+    let parent = match parent_path {
+        [] => None,
+        path => Some(get(roots, path)?.id),
+    };
+    let mut index = match position {
+        DropPosition::Before => target_index,
+        _ => target_index + 1,
+    };
+
+    // Detaching the node first shifts later siblings up by one
+    let path = path_of(roots, id)?;
+    if path.len() == target_path.len()
+        && path[..path.len() - 1] == target_path[..target_path.len() - 1]
+        && path[path.len() - 1] < index
+    {
+        index -= 1;
+    }
+    Some((parent, index))
+}
+
+/// A node as it appears in the rendered tree, with its nesting depth
+#[derive(Debug)]
+pub struct VisibleRow<'a, T> {
+    pub node: &'a Node<T>,
+    pub depth: usize,
+    /// Whether the node is shown expanded. This is the node's `open` flag,
+    /// unless a [`Filter`](crate::filter::Filter) reveals matches inside it.
+    pub open: bool,
+}
+
+impl<T> Clone for VisibleRow<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for VisibleRow<'_, T> {}
+
+/// Flattens a forest into the rows a tree view shows, in display order.
+/// Children of closed nodes are skipped.
+pub fn visible_rows<T>(roots: &[Node<T>]) -> Vec<VisibleRow<'_, T>> {
+    fn walk<'a, T>(nodes: &'a [Node<T>], depth: usize, rows: &mut Vec<VisibleRow<'a, T>>) {
+        for node in nodes {
+            rows.push(VisibleRow {
+                node,
+                depth,
+                open: node.open,
+            });
+            if node.open {
+                walk(&node.children, depth + 1, rows);
+            }
         }
     }
-    
-    col
+
+    let mut rows = Vec::new();
+    walk(roots, 0, &mut rows);
+    rows
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
 
+    /// Forest shared by the tests of the crate:
+    ///
+    /// ```text
+    /// Root (open)
+    ///   Branch
+    ///     Leaf
+    ///   Other
+    /// Second
+    /// ```
+    pub(crate) fn sample() -> Vec<Node> {
+        vec![
+            Node::new(
+                "Root",
+                true,
+                vec![
+                    Node::new("Branch", false, vec![Node::new("Leaf", false, vec![])]),
+                    Node::new("Other", false, vec![]),
+                ],
+            ),
+            Node::new("Second", false, vec![]),
+        ]
+    }
+
+    pub(crate) fn labels<T>(nodes: &[Node<T>]) -> Vec<&str> {
+        nodes.iter().map(|node| node.label.as_str()).collect()
+    }
+
     #[test]
     fn test_node_new() {
         let node = Node::new("Test Label", true, vec![]);
@@ -182,6 +524,16 @@ mod tests {
         assert_eq!(parent.children[0].label, "Child 2");
     }
 
+    #[test]
+    fn test_descendant_count() {
+        let leaf = Node::new("Leaf", false, vec![]);
+        let branch = Node::new("Branch", false, vec![leaf]);
+        let root = Node::new("Root", false, vec![branch, Node::new("Other", false, vec![])]);
+
+        assert_eq!(root.descendant_count(), 3);
+        assert_eq!(root.children[1].descendant_count(), 0);
+    }
+
     #[test]
     fn test_remove_child_invalid_index() {
         let mut parent = Node::new("Parent", false, vec![]);
@@ -268,6 +620,187 @@ mod tests {
         assert_eq!(parent.children[1].label, "Child 2");
     }
 
+    #[test]
+    fn test_node_ids_are_unique() {
+        let a = Node::new("A", false, vec![]);
+        let b = Node::new("A", false, vec![]);
+
+        assert_ne!(a.id(), b.id());
+    }
+
+    #[test]
+    fn test_find_by_id() {
+        let leaf = Node::new("Leaf", false, vec![]);
+        let leaf_id = leaf.id();
+        let root = Node::new("Root", true, vec![Node::new("Branch", false, vec![leaf])]);
+
+        assert_eq!(root.find(leaf_id).unwrap().label, "Leaf");
+        assert_eq!(root.find(root.id()).unwrap().label, "Root");
+        assert!(root.find(Node::new("Other", false, vec![]).id()).is_none());
+    }
+
+    #[test]
+    fn test_path_of_follows_structural_changes() {
+        let target = Node::new("Target", false, vec![]);
+        let id = target.id();
+        let mut roots = vec![Node::new(
+            "Root",
+            true,
+            vec![Node::new("First", false, vec![]), target],
+        )];
+
+        assert_eq!(path_of(&roots, id), Some(vec![0, 1]));
+
+        roots[0].remove_child(0);
+        assert_eq!(path_of(&roots, id), Some(vec![0, 0]));
+        assert_eq!(get(&roots, &[0, 0]).unwrap().id(), id);
+    }
+
+    #[test]
+    fn test_index_detects_moved_nodes() {
+        let target = Node::new("Target", false, vec![]);
+        let id = target.id();
+        let mut roots = vec![Node::new(
+            "Root",
+            true,
+            vec![Node::new("First", false, vec![]), target],
+        )];
+        let root = roots[0].id();
+
+        let index = Index::new(&roots);
+        assert_eq!(index.path(&roots, id), Some([0, 1].as_slice()));
+
+        roots[0].remove_child(0);
+        assert_eq!(index.path(&roots, id), None);
+        assert_eq!(index.path(&roots, root), Some([0].as_slice()));
+        assert_eq!(Index::new(&roots).path(&roots, id), Some([0, 0].as_slice()));
+    }
+
+    #[test]
+    fn test_duplicate_allocates_new_ids() {
+        let original = Node::new("Root", true, vec![Node::new("Child", false, vec![])]);
+        let copy = original.duplicate();
+
+        assert_ne!(copy.id(), original.id());
+        assert_ne!(copy.children[0].id(), original.children[0].id());
+        assert_eq!(copy.children[0].label, "Child");
+        assert!(copy.open);
+        assert_eq!(original.clone().id(), original.id());
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let roots = vec![Node::new(
+            "Root",
+            true,
+            vec![Node::new("Child", false, vec![])],
+        )];
+
+        let json = serde_json::to_string(&roots).unwrap();
+        let loaded: Vec<Node> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(loaded[0].label, "Root");
+        assert!(loaded[0].open);
+        assert_eq!(loaded[0].children[0].label, "Child");
+        assert_ne!(loaded[0].id(), roots[0].id());
+    }
+
+    #[test]
+    fn test_json_defaults_optional_fields() {
+        let loaded: Vec<Node> = serde_json::from_str(r#"[{"label": "Only"}]"#).unwrap();
+
+        assert_eq!(loaded[0].label, "Only");
+        assert!(!loaded[0].open);
+        assert!(loaded[0].children.is_empty());
+    }
+
+    #[test]
+    fn test_json_stores_pending_loads_as_unloaded() {
+        let mut roots = vec![
+            Node::new("Loaded", false, vec![]),
+            Node::new("Loading", true, vec![]),
+            Node::new("Failed", true, vec![]),
+        ];
+        roots[1].load = Load::Loading;
+        roots[2].load = Load::Failed("permission denied".into());
+
+        let json = serde_json::to_string(&roots).unwrap();
+        assert!(!json.contains("permission denied"));
+        let loaded: Vec<Node> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(loaded[0].load, Load::Loaded);
+        assert_eq!(loaded[1].load, Load::Unloaded);
+        assert_eq!(loaded[2].load, Load::Unloaded);
+    }
+
+    #[test]
+    fn test_unloaded_node_is_expandable() {
+        let mut node = Node::new("Folder", false, vec![]);
+        assert!(!node.is_expandable());
+
+        node.load = Load::Unloaded;
+        assert!(node.is_expandable());
+        assert!(node.load.can_start());
+
+        node.load = Load::Loading;
+        assert!(!node.load.can_start());
+    }
+
+    #[test]
+    fn test_node_with_data() {
+        let child = Node::with_data("Child", 7, false, vec![]);
+        let node = Node::with_data("Config", 42u32, false, vec![child]);
+
+        assert_eq!(node.label, "Config");
+        assert_eq!(node.data, 42);
+        assert_eq!(node.children[0].data, 7);
+    }
+
+    #[test]
+    fn test_node_from_data_derives_label() {
+        let node = Node::from_data(3.5f64, true, vec![]);
+
+        assert_eq!(node.label, "3.5");
+        assert_eq!(node.data, 3.5);
+    }
+
+    #[test]
+    fn test_generic_forest_lookup() {
+        let leaf = Node::with_data("Leaf", "row-17", false, vec![]);
+        let id = leaf.id();
+        let mut roots = vec![Node::with_data("Root", "row-1", true, vec![leaf])];
+
+        find_mut(&mut roots, id).unwrap().data = "row-18";
+
+        assert_eq!(find(&roots, id).unwrap().data, "row-18");
+        assert_eq!(path_of(&roots, id), Some(vec![0, 0]));
+    }
+
+    #[test]
+    fn test_visible_rows_skip_closed_children() {
+        let roots = vec![
+            Node::new(
+                "Root",
+                true,
+                vec![
+                    Node::new("Open", true, vec![Node::new("Shown", false, vec![])]),
+                    Node::new("Closed", false, vec![Node::new("Hidden", false, vec![])]),
+                ],
+            ),
+            Node::new("Second", false, vec![]),
+        ];
+
+        let rows: Vec<_> = visible_rows(&roots)
+            .iter()
+            .map(|row| (row.node.label.as_str(), row.depth))
+            .collect();
+
+        assert_eq!(
+            rows,
+            [("Root", 0), ("Open", 1), ("Shown", 2), ("Closed", 1), ("Second", 0)]
+        );
+    }
+
     #[test]
     fn test_remove_all_children() {
         let child1 = Node::new("Child 1", false, vec![]);
@@ -279,4 +812,67 @@ mod tests {
         
         assert_eq!(parent.children.len(), 0);
     }
+
+    #[test]
+    fn test_move_node_reparents_subtree() {
+        let mut roots = sample();
+        let branch = roots[0].children[0].id();
+        let second = roots[1].id();
+
+        let from = move_node(&mut roots, branch, Some(second), 0);
+
+        assert_eq!(from, Some((Some(roots[0].id()), 0)));
+        assert_eq!(labels(&roots[0].children), ["Other"]);
+        assert_eq!(labels(&roots[1].children), ["Branch"]);
+        assert_eq!(roots[1].children[0].children[0].label, "Leaf");
+    }
+
+    #[test]
+    fn test_move_node_to_roots() {
+        let mut roots = sample();
+        let other = roots[0].children[1].id();
+
+        move_node(&mut roots, other, None, 1);
+
+        assert_eq!(labels(&roots), ["Root", "Other", "Second"]);
+    }
+
+    #[test]
+    fn test_move_node_refuses_own_subtree() {
+        let mut roots = sample();
+        let root = roots[0].id();
+        let leaf = roots[0].children[0].children[0].id();
+
+        assert_eq!(move_node(&mut roots, root, Some(leaf), 0), None);
+        assert_eq!(move_node(&mut roots, root, Some(root), 0), None);
+        assert_eq!(labels(&roots), ["Root", "Second"]);
+    }
+
+    #[test]
+    fn test_drop_destination_positions() {
+        let roots = sample();
+        let root = roots[0].id();
+        let branch = roots[0].children[0].id();
+        let other = roots[0].children[1].id();
+        let second = roots[1].id();
+
+        assert_eq!(
+            drop_destination(&roots, second, branch, DropPosition::Before),
+            Some((Some(root), 0))
+        );
+        assert_eq!(
+            drop_destination(&roots, second, branch, DropPosition::After),
+            Some((Some(root), 1))
+        );
+        assert_eq!(
+            drop_destination(&roots, second, branch, DropPosition::Inside),
+            Some((Some(branch), usize::MAX))
+        );
+        // Moving Branch below Other among its own siblings
+        assert_eq!(
+            drop_destination(&roots, branch, other, DropPosition::After),
+            Some((Some(root), 1))
+        );
+        assert_eq!(drop_destination(&roots, root, branch, DropPosition::After), None);
+    }
 }
@@ -0,0 +1,1503 @@
+use std::ops::Range;
+
+use iced::advanced::layout::{self, Layout};
+use iced::advanced::mouse::{self, click};
+use iced::advanced::renderer::{self, Renderer as _};
+use iced::advanced::text::{self, Paragraph as _, Renderer as _};
+use iced::advanced::widget::{self, Operation, Tree, Widget};
+use iced::advanced::{Clipboard, Shell};
+use iced::alignment;
+use iced::keyboard;
+use iced::time::{Duration, Instant};
+use iced::widget::text_input;
+use iced::window;
+use iced::{
+    Border, Color, Element, Event, Length, Pixels, Point, Rectangle, Renderer, Size, Theme,
+};
+
+use crate::checks::CheckState;
+use crate::filter::Filter;
+use crate::selection::{SelectMode, Selection};
+use crate::tree::{DropPosition, Load, Node, NodeId, VisibleRow};
+
+const DEFAULT_INDENT: f32 = 24.0;
+/// Distance the cursor must travel with the button held before a drag starts
+const DRAG_THRESHOLD: f32 = 4.0;
+/// How long a dragged node has to hover over a closed node to expand it
+const AUTO_EXPAND_DELAY: Duration = Duration::from_millis(700);
+/// Row height used unless overridden with [`TreeView::row_height`]
+pub const DEFAULT_ROW_HEIGHT: f32 = 24.0;
+
+/// Handler for a dragged node dropped on another one
+type OnDrop<'a, Message> = Box<dyn Fn(NodeId, NodeId, DropPosition) -> Message + 'a>;
+
+/// Reusable tree widget that maps its events into the caller's message type
+///
+/// Rows are laid out and drawn directly by the widget rather than being
+/// built from nested buttons, so the per-frame cost does not grow with the
+/// number of widgets per node. Only rows intersecting the viewport are drawn,
+/// which keeps very large trees interactive when placed in a `scrollable`.
+///
+/// ```ignore
+/// TreeView::new(&roots)
+///     .on_toggle(Message::Toggle)
+///     .on_rename(Message::StartEdit)
+///     .indent(16.0)
+/// ```
+pub struct TreeView<'a, Message> {
+    rows: Vec<Row<'a>>,
+    on_toggle: Option<Box<dyn Fn(NodeId) -> Message + 'a>>,
+    on_select: Option<Box<dyn Fn(NodeId, SelectMode) -> Message + 'a>>,
+    on_rename: Option<Box<dyn Fn(NodeId) -> Message + 'a>>,
+    on_add: Option<Box<dyn Fn(NodeId) -> Message + 'a>>,
+    on_remove: Option<Box<dyn Fn(NodeId) -> Message + 'a>>,
+    on_drop: Option<OnDrop<'a, Message>>,
+    on_retry: Option<Box<dyn Fn(NodeId) -> Message + 'a>>,
+    on_check: Option<Box<dyn Fn(NodeId) -> Message + 'a>>,
+    on_edit: Option<Box<dyn Fn(String) -> Message + 'a>>,
+    on_submit: Option<Message>,
+    on_cancel: Option<Message>,
+    on_blur: Option<Message>,
+    /// Index of the row being edited
+    editing: Option<usize>,
+    edit_value: &'a str,
+    editor: Option<Element<'a, Message>>,
+    selection: Option<&'a Selection>,
+    focused: Option<NodeId>,
+    indent: f32,
+    spacing: f32,
+    row_height: f32,
+    text_size: Option<Pixels>,
+}
+
+/// Extra content drawn around a node's label, see [`TreeView::decorated`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Decoration<'a> {
+    /// Drawn between the expand arrow and the label
+    pub icon: Option<&'a str>,
+    /// Drawn dimmed after the label, e.g. to explain an error
+    pub note: Option<&'a str>,
+    /// Checkbox drawn between the expand arrow and the icon
+    pub check: Option<CheckState>,
+}
+
+/// A visible node, flattened out of the forest when the view is built, or
+/// a placeholder for the children of an open node that are not loaded
+struct Row<'a> {
+    /// For placeholders, the id of the node whose children are missing
+    id: NodeId,
+    kind: RowKind,
+    label: &'a str,
+    depth: usize,
+    open: bool,
+    has_children: bool,
+    decoration: Decoration<'a>,
+    /// Byte range of the label matched by the filter
+    highlight: Option<Range<usize>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RowKind {
+    Node,
+    /// The node's children are being loaded
+    Loading,
+    /// Loading the node's children failed; the label is the reason
+    Failed,
+}
+
+/// The part of a row the cursor is over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Arrow,
+    Label,
+    Rename,
+    Add,
+    Remove,
+    /// A failed placeholder row, which retries loading when clicked
+    Retry,
+    Check,
+}
+
+impl Target {
+    /// Icon drawn for a row button
+    fn icon(self) -> &'static str {
+        match self {
+            Target::Rename => "✏",
+            Target::Add => "+",
+            Target::Remove => "✖",
+            Target::Retry => "↻",
+            Target::Arrow | Target::Label | Target::Check => "",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    hovered: Option<usize>,
+    modifiers: keyboard::Modifiers,
+    last_click: Option<mouse::Click>,
+    drag: Option<Drag>,
+}
+
+/// A press on a label that may turn into a drag
+#[derive(Debug, Clone, Copy)]
+struct Drag {
+    id: NodeId,
+    origin: Point,
+    /// Set once the cursor has moved past the drag threshold
+    active: bool,
+    target: Option<DropTarget>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct DropTarget {
+    id: NodeId,
+    position: DropPosition,
+    /// When the cursor entered the target row
+    since: Instant,
+    /// Whether the auto-expand toggle has been sent for this hover
+    expanded: bool,
+}
+
+impl<'a, Message: Clone + 'a> TreeView<'a, Message> {
+    pub fn new<T>(roots: &'a [Node<T>]) -> Self {
+        Self::decorated(roots, |_| Decoration::default())
+    }
+
+    /// Creates a tree view that asks `decorate` for an icon and a note to
+    /// draw with each visible node
+    pub fn decorated<T>(
+        roots: &'a [Node<T>],
+        decorate: impl Fn(&'a Node<T>) -> Decoration<'a>,
+    ) -> Self {
+        Self::filtered(roots, &Filter::default(), decorate)
+    }
+
+    /// Creates a decorated tree view showing only the rows `filter` lets
+    /// through, with the matched part of each label highlighted
+    pub fn filtered<T>(
+        roots: &'a [Node<T>],
+        filter: &Filter,
+        decorate: impl Fn(&'a Node<T>) -> Decoration<'a>,
+    ) -> Self {
+        let mut rows = Vec::new();
+        for row in filter.rows(roots) {
+            let node = row.node;
+            rows.push(Row {
+                id: node.id(),
+                kind: RowKind::Node,
+                label: &node.label,
+                depth: row.depth,
+                open: row.open,
+                has_children: node.is_expandable(),
+                decoration: decorate(node),
+                highlight: filter.find(&node.label),
+            });
+
+            if let Some((kind, label, icon)) = placeholder(&row) {
+                rows.push(Row {
+                    id: node.id(),
+                    kind,
+                    label,
+                    depth: row.depth + 1,
+                    open: false,
+                    has_children: false,
+                    decoration: Decoration {
+                        icon: Some(icon),
+                        ..Decoration::default()
+                    },
+                    highlight: None,
+                });
+            }
+        }
+
+        Self {
+            rows,
+            on_toggle: None,
+            on_select: None,
+            on_rename: None,
+            on_add: None,
+            on_remove: None,
+            on_drop: None,
+            on_retry: None,
+            on_check: None,
+            on_edit: None,
+            on_submit: None,
+            on_cancel: None,
+            on_blur: None,
+            editing: None,
+            edit_value: "",
+            editor: None,
+            selection: None,
+            focused: None,
+            indent: DEFAULT_INDENT,
+            spacing: 0.0,
+            row_height: DEFAULT_ROW_HEIGHT,
+            text_size: None,
+        }
+    }
+
+    /// Message emitted when a node's expand arrow is clicked.
+    /// Also emitted for label clicks when no `on_select` handler is set.
+    pub fn on_toggle(mut self, f: impl Fn(NodeId) -> Message + 'a) -> Self {
+        self.on_toggle = Some(Box::new(f));
+        self
+    }
+
+    /// Message emitted when a node's label is clicked, with the selection
+    /// mode derived from the keyboard modifiers held at the time
+    pub fn on_select(mut self, f: impl Fn(NodeId, SelectMode) -> Message + 'a) -> Self {
+        self.on_select = Some(Box::new(f));
+        self
+    }
+
+    /// Highlights the rows of the selected nodes
+    pub fn selection(mut self, selection: &'a Selection) -> Self {
+        self.selection = Some(selection);
+        self
+    }
+
+    /// Message emitted when the rename (✏) button of a node is clicked, or
+    /// its label is double-clicked while `on_select` is set.
+    /// The button is only shown when this handler is set.
+    pub fn on_rename(mut self, f: impl Fn(NodeId) -> Message + 'a) -> Self {
+        self.on_rename = Some(Box::new(f));
+        self
+    }
+
+    /// Message emitted when the add (+) button of a node is clicked.
+    /// The button is only shown when this handler is set.
+    pub fn on_add(mut self, f: impl Fn(NodeId) -> Message + 'a) -> Self {
+        self.on_add = Some(Box::new(f));
+        self
+    }
+
+    /// Message emitted when the remove (✖) button of a node is clicked.
+    /// The button is only shown when this handler is set.
+    pub fn on_remove(mut self, f: impl Fn(NodeId) -> Message + 'a) -> Self {
+        self.on_remove = Some(Box::new(f));
+        self
+    }
+
+    /// Enables dragging rows by their label. The message receives the
+    /// dragged node, the node it was dropped on and where relative to it.
+    /// Drops into the dragged node's own subtree are not offered.
+    pub fn on_drop(mut self, f: impl Fn(NodeId, NodeId, DropPosition) -> Message + 'a) -> Self {
+        self.on_drop = Some(Box::new(f));
+        self
+    }
+
+    /// Message emitted when the row shown for a node whose children failed
+    /// to load is clicked
+    pub fn on_retry(mut self, f: impl Fn(NodeId) -> Message + 'a) -> Self {
+        self.on_retry = Some(Box::new(f));
+        self
+    }
+
+    /// Message emitted when the checkbox of a node is clicked. Checkboxes
+    /// are drawn for nodes whose [`Decoration`] has a `check` state.
+    pub fn on_check(mut self, f: impl Fn(NodeId) -> Message + 'a) -> Self {
+        self.on_check = Some(Box::new(f));
+        self
+    }
+
+    /// Shows a text input with `value` in place of the label of `id`
+    pub fn editing(mut self, id: impl Into<Option<NodeId>>, value: &'a str) -> Self {
+        let id = id.into();
+        self.editing = id.and_then(|id| self.rows.iter().position(|row| row.id == id));
+        self.edit_value = value;
+        self
+    }
+
+    /// Message emitted for every change in the label text input
+    pub fn on_edit(mut self, f: impl Fn(String) -> Message + 'a) -> Self {
+        self.on_edit = Some(Box::new(f));
+        self
+    }
+
+    /// Message emitted when the label text input is submitted
+    pub fn on_submit(mut self, message: Message) -> Self {
+        self.on_submit = Some(message);
+        self
+    }
+
+    /// Message emitted when Escape is pressed while a label is being edited
+    pub fn on_cancel(mut self, message: Message) -> Self {
+        self.on_cancel = Some(message);
+        self
+    }
+
+    /// Message emitted when the mouse is pressed outside the label text input
+    /// while it is shown. Any message for the click itself follows it.
+    pub fn on_blur(mut self, message: Message) -> Self {
+        self.on_blur = Some(message);
+        self
+    }
+
+    /// Draws the keyboard cursor around the row of the given node
+    pub fn focused(mut self, id: impl Into<Option<NodeId>>) -> Self {
+        self.focused = id.into();
+        self
+    }
+
+    /// Horizontal offset applied per nesting level
+    pub fn indent(mut self, indent: f32) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    /// Vertical space between rows
+    pub fn spacing(mut self, spacing: f32) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Height of a single row
+    pub fn row_height(mut self, row_height: f32) -> Self {
+        self.row_height = row_height;
+        self
+    }
+
+    /// Size of the label text; defaults to the renderer's text size
+    pub fn text_size(mut self, size: impl Into<Pixels>) -> Self {
+        self.text_size = Some(size.into());
+        self
+    }
+
+    /// Builds the text input shown in place of the label being edited
+    fn build_editor(&mut self) {
+        let Some(row) = self.editing.map(|index| &self.rows[index]) else {
+            return;
+        };
+
+        let mut input = text_input(&format!("Edit {}", row.label), self.edit_value).padding(2);
+        if let Some(on_edit) = self.on_edit.take() {
+            input = input.on_input(on_edit);
+        }
+        if let Some(on_submit) = self.on_submit.clone() {
+            input = input.on_submit(on_submit);
+        }
+        if let Some(size) = self.text_size {
+            input = input.size(size);
+        }
+        self.editor = Some(input.into());
+    }
+
+    /// Distance between the tops of two consecutive rows
+    fn pitch(&self) -> f32 {
+        self.row_height + self.spacing
+    }
+
+    fn row_bounds(&self, bounds: Rectangle, index: usize) -> Rectangle {
+        Rectangle {
+            x: bounds.x,
+            y: bounds.y + index as f32 * self.pitch(),
+            width: bounds.width,
+            height: self.row_height,
+        }
+    }
+
+    fn arrow_bounds(&self, row_bounds: Rectangle, row: &Row<'_>) -> Rectangle {
+        Rectangle {
+            x: row_bounds.x + row.depth as f32 * self.indent,
+            width: self.row_height,
+            ..row_bounds
+        }
+    }
+
+    fn check_bounds(&self, row_bounds: Rectangle, row: &Row<'_>) -> Rectangle {
+        let arrow = self.arrow_bounds(row_bounds, row);
+        Rectangle {
+            x: arrow.x + arrow.width,
+            width: self.row_height,
+            ..row_bounds
+        }
+    }
+
+    /// Where the row's icon starts, or its label when it has no icon
+    fn content_x(&self, row_bounds: Rectangle, row: &Row<'_>) -> f32 {
+        let check = self.check_bounds(row_bounds, row);
+        if row.decoration.check.is_some() {
+            check.x + check.width
+        } else {
+            check.x
+        }
+    }
+
+    fn icon_bounds(&self, row_bounds: Rectangle, row: &Row<'_>) -> Rectangle {
+        Rectangle {
+            x: self.content_x(row_bounds, row),
+            width: self.row_height,
+            ..row_bounds
+        }
+    }
+
+    fn label_x(&self, row_bounds: Rectangle, row: &Row<'_>) -> f32 {
+        let icon = if row.decoration.icon.is_some() {
+            self.row_height
+        } else {
+            0.0
+        };
+        self.content_x(row_bounds, row) + icon
+    }
+
+    /// Row buttons with a handler set, from the right edge inwards
+    fn buttons(&self) -> impl Iterator<Item = Target> + '_ {
+        [
+            (Target::Rename, self.on_rename.is_some()),
+            (Target::Add, self.on_add.is_some()),
+            (Target::Remove, self.on_remove.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(target, enabled)| enabled.then_some(target))
+    }
+
+    /// Bounds of the `slot`-th button counted from the right edge of a row
+    fn button_bounds(&self, row_bounds: Rectangle, slot: usize) -> Rectangle {
+        Rectangle {
+            x: row_bounds.x + row_bounds.width - (slot + 1) as f32 * self.row_height,
+            width: self.row_height,
+            ..row_bounds
+        }
+    }
+
+    /// Range of row indices that intersect `region`
+    fn visible_range(&self, bounds: Rectangle, region: &Rectangle) -> Range<usize> {
+        let top = (region.y - bounds.y).max(0.0);
+        let bottom = (region.y + region.height - bounds.y).max(0.0);
+
+        let first = (top / self.pitch()) as usize;
+        let last = (bottom / self.pitch()).ceil() as usize;
+        first.min(self.rows.len())..last.min(self.rows.len())
+    }
+
+    /// Finds the row under a point and which part of it was hit
+    fn hit(&self, bounds: Rectangle, point: Point) -> Option<(usize, Target)> {
+        if !bounds.contains(point) {
+            return None;
+        }
+
+        let offset = point.y - bounds.y;
+        let index = (offset / self.pitch()) as usize;
+        if offset - index as f32 * self.pitch() > self.row_height {
+            // Between rows
+            return None;
+        }
+        let row = self.rows.get(index)?;
+        let row_bounds = self.row_bounds(bounds, index);
+
+        match row.kind {
+            RowKind::Node => {}
+            RowKind::Failed if self.on_retry.is_some() => return Some((index, Target::Retry)),
+            RowKind::Loading | RowKind::Failed => return None,
+        }
+
+        let button = self
+            .buttons()
+            .enumerate()
+            .find(|&(slot, _)| self.button_bounds(row_bounds, slot).contains(point));
+
+        let target = if let Some((_, button)) = button {
+            button
+        } else if row.has_children && self.arrow_bounds(row_bounds, row).contains(point) {
+            Target::Arrow
+        } else if row.decoration.check.is_some()
+            && self.on_check.is_some()
+            && self.check_bounds(row_bounds, row).contains(point)
+        {
+            Target::Check
+        } else if point.x >= self.content_x(row_bounds, row) {
+            Target::Label
+        } else {
+            return None;
+        };
+        Some((index, target))
+    }
+
+    /// Finds the row a dragged node would be dropped on and where. The
+    /// dragged row and its descendants are never targets.
+    fn drop_target(
+        &self,
+        bounds: Rectangle,
+        point: Point,
+        dragged: NodeId,
+    ) -> Option<(usize, DropPosition)> {
+        if !bounds.contains(point) {
+            return None;
+        }
+
+        let offset = point.y - bounds.y;
+        let index = (offset / self.pitch()) as usize;
+        if self.rows.get(index)?.kind != RowKind::Node {
+            return None;
+        }
+
+        let start = self.rows.iter().position(|row| row.id == dragged)?;
+        let depth = self.rows[start].depth;
+        let end = self.rows[start + 1..]
+            .iter()
+            .position(|row| row.depth <= depth)
+            .map_or(self.rows.len(), |count| start + 1 + count);
+        if (start..end).contains(&index) {
+            return None;
+        }
+
+        let within = (offset - index as f32 * self.pitch()) / self.row_height;
+        let position = if within < 0.25 {
+            DropPosition::Before
+        } else if within > 0.75 {
+            DropPosition::After
+        } else {
+            DropPosition::Inside
+        };
+        Some((index, position))
+    }
+
+    fn text(&self, renderer: &Renderer, content: &str, bounds: Size) -> text::Text {
+        text::Text {
+            content: content.to_string(),
+            bounds,
+            size: self.text_size.unwrap_or_else(|| renderer.default_size()),
+            line_height: text::LineHeight::default(),
+            font: renderer.default_font(),
+            align_x: text::Alignment::Left,
+            align_y: alignment::Vertical::Center,
+            shaping: text::Shaping::Advanced,
+            wrapping: text::Wrapping::None,
+        }
+    }
+}
+
+impl<'a, Message: Clone + 'a> Widget<Message, Theme, Renderer> for TreeView<'a, Message> {
+    fn size(&self) -> Size<Length> {
+        Size::new(Length::Fill, Length::Shrink)
+    }
+
+    fn tag(&self) -> widget::tree::Tag {
+        widget::tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> widget::tree::State {
+        widget::tree::State::new(State::default())
+    }
+
+    fn children(&self) -> Vec<Tree> {
+        self.editor
+            .iter()
+            .map(|editor| {
+                let mut tree = Tree::new(editor);
+                focus_editor(&mut tree);
+                tree
+            })
+            .collect()
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        match &self.editor {
+            Some(editor) => {
+                // A freshly shown editor takes keyboard focus right away
+                let is_new = tree.children.is_empty();
+                tree.diff_children(std::slice::from_ref(editor));
+                if is_new {
+                    focus_editor(&mut tree.children[0]);
+                }
+            }
+            None => tree.children.clear(),
+        }
+    }
+
+    fn layout(
+        &mut self,
+        tree: &mut Tree,
+        renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        let height = (self.rows.len() as f32 * self.pitch() - self.spacing).max(0.0);
+        let size = limits.resolve(Length::Fill, Length::Shrink, Size::new(0.0, height));
+
+        let Some(index) = self.editing.filter(|_| self.editor.is_some()) else {
+            return layout::Node::new(size);
+        };
+
+        let row_bounds = self.row_bounds(Rectangle::with_size(size), index);
+        let x = self.label_x(row_bounds, &self.rows[index]);
+        let editor = self.editor.as_mut().expect("editor is present");
+        let editor_limits = layout::Limits::new(
+            Size::ZERO,
+            Size::new((size.width - x).max(0.0), self.row_height),
+        );
+        let editor_node = editor
+            .as_widget_mut()
+            .layout(&mut tree.children[0], renderer, &editor_limits)
+            .move_to(Point::new(x, row_bounds.y));
+
+        layout::Node::with_children(size, vec![editor_node])
+    }
+
+    fn operate(
+        &mut self,
+        tree: &mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn Operation,
+    ) {
+        if let (Some(editor), Some(editor_layout)) = (&mut self.editor, layout.children().next()) {
+            editor.as_widget_mut().operate(
+                &mut tree.children[0],
+                editor_layout,
+                renderer,
+                operation,
+            );
+        }
+    }
+
+    fn update(
+        &mut self,
+        tree: &mut Tree,
+        event: &Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) {
+        if let (Some(editor), Some(editor_layout)) = (&mut self.editor, layout.children().next()) {
+            // The text input swallows Escape to drop its own focus, so it has
+            // to be intercepted before the event is forwarded
+            match event {
+                Event::Keyboard(keyboard::Event::KeyPressed {
+                    key: keyboard::Key::Named(keyboard::key::Named::Escape),
+                    ..
+                }) => {
+                    if let Some(on_cancel) = self.on_cancel.clone() {
+                        shell.publish(on_cancel);
+                        shell.capture_event();
+                        return;
+                    }
+                }
+                Event::Mouse(mouse::Event::ButtonPressed(_))
+                    if !cursor.is_over(editor_layout.bounds()) =>
+                {
+                    if let Some(on_blur) = self.on_blur.clone() {
+                        shell.publish(on_blur);
+                    }
+                }
+                _ => {}
+            }
+
+            editor.as_widget_mut().update(
+                &mut tree.children[0],
+                event,
+                editor_layout,
+                cursor,
+                renderer,
+                clipboard,
+                shell,
+                viewport,
+            );
+            if shell.is_event_captured() {
+                return;
+            }
+        }
+
+        let bounds = layout.bounds();
+        let state = tree.state.downcast_mut::<State>();
+
+        match event {
+            Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                state.modifiers = *modifiers;
+            }
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key: keyboard::Key::Named(keyboard::key::Named::Escape),
+                ..
+            }) if state.drag.is_some_and(|drag| drag.active) => {
+                state.drag = None;
+                shell.request_redraw();
+                shell.capture_event();
+            }
+            Event::Mouse(mouse::Event::CursorMoved { .. } | mouse::Event::CursorLeft) => {
+                let hovered = cursor
+                    .position()
+                    .and_then(|position| self.hit(bounds, position))
+                    .map(|(index, _)| index);
+                if hovered != state.hovered {
+                    state.hovered = hovered;
+                    shell.request_redraw();
+                }
+
+                let (Some(drag), Some(position)) = (&mut state.drag, cursor.position()) else {
+                    return;
+                };
+                if !drag.active && position.distance(drag.origin) > DRAG_THRESHOLD {
+                    drag.active = true;
+                }
+                if !drag.active {
+                    return;
+                }
+
+                let target = self
+                    .drop_target(bounds, position, drag.id)
+                    .map(|(index, position)| (self.rows[index].id, position));
+                drag.target = match (drag.target, target) {
+                    (Some(current), Some((id, position))) if current.id == id => Some(DropTarget {
+                        position,
+                        ..current
+                    }),
+                    (_, Some((id, position))) => Some(DropTarget {
+                        id,
+                        position,
+                        since: Instant::now(),
+                        expanded: false,
+                    }),
+                    (_, None) => None,
+                };
+                if let Some(target) = drag.target {
+                    shell.request_redraw_at(target.since + AUTO_EXPAND_DELAY);
+                }
+                shell.request_redraw();
+            }
+            Event::Window(window::Event::RedrawRequested(now)) => {
+                let Some(target) = state.drag.as_mut().and_then(|drag| drag.target.as_mut()) else {
+                    return;
+                };
+                let Some(row) = self.rows.iter().find(|row| row.id == target.id) else {
+                    return;
+                };
+                let Some(on_toggle) = &self.on_toggle else {
+                    return;
+                };
+                if !target.expanded
+                    && target.position == DropPosition::Inside
+                    && row.has_children
+                    && !row.open
+                    && *now >= target.since + AUTO_EXPAND_DELAY
+                {
+                    target.expanded = true;
+                    shell.publish(on_toggle(row.id));
+                }
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                let Some(drag) = state.drag.take() else {
+                    return;
+                };
+                if !drag.active {
+                    return;
+                }
+                if let (Some(target), Some(on_drop)) = (drag.target, &self.on_drop) {
+                    shell.publish(on_drop(drag.id, target.id, target.position));
+                }
+                shell.request_redraw();
+                shell.capture_event();
+            }
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                let Some(position) = cursor.position() else {
+                    return;
+                };
+                let Some((index, target)) = self.hit(bounds, position) else {
+                    return;
+                };
+                let id = self.rows[index].id;
+
+                let message = match target {
+                    Target::Arrow => self.on_toggle.as_ref().map(|f| f(id)),
+                    Target::Rename => self.on_rename.as_ref().map(|f| f(id)),
+                    Target::Add => self.on_add.as_ref().map(|f| f(id)),
+                    Target::Remove => self.on_remove.as_ref().map(|f| f(id)),
+                    Target::Retry => self.on_retry.as_ref().map(|f| f(id)),
+                    Target::Check => self.on_check.as_ref().map(|f| f(id)),
+                    Target::Label => {
+                        let click =
+                            mouse::Click::new(position, mouse::Button::Left, state.last_click);
+                        state.last_click = Some(click);
+                        if self.on_drop.is_some() {
+                            state.drag = Some(Drag {
+                                id,
+                                origin: position,
+                                active: false,
+                                target: None,
+                            });
+                        }
+
+                        match (&self.on_select, &self.on_rename) {
+                            (Some(_), Some(on_rename)) if click.kind() == click::Kind::Double => {
+                                Some(on_rename(id))
+                            }
+                            (Some(on_select), _) => {
+                                Some(on_select(id, SelectMode::from_modifiers(state.modifiers)))
+                            }
+                            (None, _) => self.on_toggle.as_ref().map(|f| f(id)),
+                        }
+                    }
+                };
+
+                if let Some(message) = message {
+                    shell.publish(message);
+                    shell.capture_event();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        if let (Some(editor), Some(editor_layout)) = (&self.editor, layout.children().next()) {
+            let interaction = editor.as_widget().mouse_interaction(
+                &tree.children[0],
+                editor_layout,
+                cursor,
+                viewport,
+                renderer,
+            );
+            if interaction != mouse::Interaction::None {
+                return interaction;
+            }
+        }
+
+        let state = tree.state.downcast_ref::<State>();
+        if state.drag.is_some_and(|drag| drag.active) {
+            return mouse::Interaction::Grabbing;
+        }
+
+        match cursor
+            .position()
+            .and_then(|position| self.hit(layout.bounds(), position))
+        {
+            Some(_) => mouse::Interaction::Pointer,
+            None => mouse::Interaction::None,
+        }
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        let Some(clip) = bounds.intersection(viewport) else {
+            return;
+        };
+        let palette = theme.extended_palette();
+        let hovered = cursor
+            .position()
+            .and_then(|position| self.hit(bounds, position))
+            .map(|(index, _)| index);
+
+        // Only rows intersecting the viewport are drawn
+        for index in self.visible_range(bounds, &clip) {
+            let row = &self.rows[index];
+            let row_bounds = self.row_bounds(bounds, index);
+
+            let is_node = row.kind == RowKind::Node;
+            let selected = is_node
+                && self
+                    .selection
+                    .is_some_and(|selection| selection.is_selected(row.id));
+            let background = if selected {
+                Some(palette.primary.weak.color)
+            } else if hovered == Some(index) {
+                Some(palette.background.weak.color)
+            } else {
+                None
+            };
+            let text_color = if selected {
+                palette.primary.weak.text
+            } else if is_node {
+                style.text_color
+            } else {
+                Color {
+                    a: 0.6,
+                    ..style.text_color
+                }
+            };
+
+            if let Some(background) = background {
+                renderer.fill_quad(
+                    renderer::Quad {
+                        bounds: row_bounds,
+                        border: Border::default().rounded(4),
+                        ..renderer::Quad::default()
+                    },
+                    background,
+                );
+            }
+
+            if is_node && self.focused == Some(row.id) {
+                renderer.fill_quad(
+                    renderer::Quad {
+                        bounds: row_bounds,
+                        border: Border::default()
+                            .rounded(4)
+                            .width(1)
+                            .color(palette.primary.strong.color),
+                        ..renderer::Quad::default()
+                    },
+                    Color::TRANSPARENT,
+                );
+            }
+
+            if row.has_children {
+                let arrow = self.arrow_bounds(row_bounds, row);
+                let icon = if row.open { "▼" } else { "▶" };
+                renderer.fill_text(
+                    text::Text {
+                        align_x: text::Alignment::Center,
+                        ..self.text(renderer, icon, arrow.size())
+                    },
+                    arrow.center(),
+                    text_color,
+                    clip,
+                );
+            }
+
+            if let Some(check) = row.decoration.check {
+                let check_bounds = self.check_bounds(row_bounds, row);
+                let size = (self.row_height * 0.6).round();
+                let check_box = Rectangle {
+                    x: check_bounds.center_x() - size / 2.0,
+                    y: check_bounds.center_y() - size / 2.0,
+                    width: size,
+                    height: size,
+                };
+                let (background, mark) = match check {
+                    CheckState::Unchecked => (Color::TRANSPARENT, None),
+                    CheckState::Checked => (palette.primary.strong.color, Some("✓")),
+                    CheckState::Indeterminate => (palette.primary.strong.color, Some("–")),
+                };
+                renderer.fill_quad(
+                    renderer::Quad {
+                        bounds: check_box,
+                        border: Border::default()
+                            .rounded(3)
+                            .width(1)
+                            .color(palette.primary.strong.color),
+                        ..renderer::Quad::default()
+                    },
+                    background,
+                );
+                if let Some(mark) = mark {
+                    renderer.fill_text(
+                        text::Text {
+                            align_x: text::Alignment::Center,
+                            ..self.text(renderer, mark, check_box.size())
+                        },
+                        check_box.center(),
+                        palette.primary.strong.text,
+                        clip,
+                    );
+                }
+            }
+
+            if let Some(icon) = row.decoration.icon {
+                let icon_bounds = self.icon_bounds(row_bounds, row);
+                renderer.fill_text(
+                    text::Text {
+                        align_x: text::Alignment::Center,
+                        ..self.text(renderer, icon, icon_bounds.size())
+                    },
+                    icon_bounds.center(),
+                    text_color,
+                    clip,
+                );
+            }
+
+            if self.editing == Some(index) {
+                continue;
+            }
+
+            let label_x = self.label_x(row_bounds, row);
+            let label_width = row_bounds.x + row_bounds.width - label_x;
+            let label = self.text(
+                renderer,
+                row.label,
+                Size::new(label_width, row_bounds.height),
+            );
+
+            if let Some(range) = row.highlight.clone() {
+                let width = |end: usize| {
+                    <Renderer as text::Renderer>::Paragraph::with_text(text::Text {
+                        content: &row.label[..end],
+                        ..label.as_ref()
+                    })
+                    .min_width()
+                };
+                let start = width(range.start);
+                renderer.fill_quad(
+                    renderer::Quad {
+                        bounds: Rectangle {
+                            x: label_x + start,
+                            y: row_bounds.y + 2.0,
+                            width: width(range.end) - start,
+                            height: row_bounds.height - 4.0,
+                        },
+                        border: Border::default().rounded(2),
+                        ..renderer::Quad::default()
+                    },
+                    palette.warning.weak.color,
+                );
+            }
+
+            if let Some(note) = row.decoration.note {
+                let paragraph = <Renderer as text::Renderer>::Paragraph::with_text(label.as_ref());
+                let note_x = label_x + paragraph.min_width() + self.row_height / 2.0;
+                renderer.fill_text(
+                    self.text(
+                        renderer,
+                        note,
+                        Size::new(
+                            (row_bounds.x + row_bounds.width - note_x).max(0.0),
+                            row_bounds.height,
+                        ),
+                    ),
+                    Point::new(note_x, row_bounds.center_y()),
+                    Color {
+                        a: 0.6,
+                        ..text_color
+                    },
+                    clip,
+                );
+            }
+
+            renderer.fill_text(
+                label,
+                Point::new(label_x, row_bounds.center_y()),
+                text_color,
+                clip,
+            );
+
+            if hovered != Some(index) {
+                continue;
+            }
+            let buttons: Vec<_> = if is_node {
+                self.buttons().collect()
+            } else {
+                vec![Target::Retry]
+            };
+            for (slot, button) in buttons.into_iter().enumerate() {
+                let button_bounds = self.button_bounds(row_bounds, slot);
+                renderer.fill_text(
+                    text::Text {
+                        align_x: text::Alignment::Center,
+                        ..self.text(renderer, button.icon(), button_bounds.size())
+                    },
+                    button_bounds.center(),
+                    Color {
+                        a: 0.7,
+                        ..text_color
+                    },
+                    clip,
+                );
+            }
+        }
+
+        let target = tree
+            .state
+            .downcast_ref::<State>()
+            .drag
+            .and_then(|drag| drag.target)
+            .and_then(|target| {
+                let index = self.rows.iter().position(|row| row.id == target.id)?;
+                Some((index, target.position))
+            });
+        if let Some((index, position)) = target {
+            let row_bounds = self.row_bounds(bounds, index);
+            let label_x = self.label_x(row_bounds, &self.rows[index]);
+            let line = |y: f32| Rectangle {
+                x: label_x,
+                y: y - 1.0,
+                width: row_bounds.x + row_bounds.width - label_x,
+                height: 2.0,
+            };
+            let (bounds, border, background) = match position {
+                DropPosition::Before => (
+                    line(row_bounds.y),
+                    Border::default(),
+                    palette.primary.strong.color,
+                ),
+                DropPosition::After => (
+                    line(row_bounds.y + row_bounds.height),
+                    Border::default(),
+                    palette.primary.strong.color,
+                ),
+                DropPosition::Inside => (
+                    row_bounds,
+                    Border::default()
+                        .rounded(4)
+                        .width(2)
+                        .color(palette.primary.strong.color),
+                    Color::TRANSPARENT,
+                ),
+            };
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds,
+                    border,
+                    ..renderer::Quad::default()
+                },
+                background,
+            );
+        }
+
+        if let (Some(editor), Some(editor_layout)) = (&self.editor, layout.children().next()) {
+            editor.as_widget().draw(
+                &tree.children[0],
+                renderer,
+                theme,
+                style,
+                editor_layout,
+                cursor,
+                viewport,
+            );
+        }
+    }
+}
+
+/// Index of each of the given node rows among the rows a tree view draws
+/// for them, which include a placeholder row below every open node that is
+/// loading or failed to load
+pub fn row_indices<'a, T>(rows: &'a [VisibleRow<'a, T>]) -> impl Iterator<Item = usize> + 'a {
+    rows.iter().scan(0, |next, row| {
+        let index = *next;
+        *next += 1 + usize::from(placeholder(row).is_some());
+        Some(index)
+    })
+}
+
+/// Kind, label and icon of the placeholder row drawn below a node row, if
+/// it has one
+fn placeholder<'a, T>(row: &VisibleRow<'a, T>) -> Option<(RowKind, &'a str, &'static str)> {
+    if !row.open {
+        return None;
+    }
+    match &row.node.load {
+        Load::Loading => Some((RowKind::Loading, "Loading…", "⏳")),
+        Load::Failed(reason) => Some((RowKind::Failed, reason.as_str(), "⚠")),
+        Load::Loaded | Load::Unloaded => None,
+    }
+}
+
+/// Gives keyboard focus to the label editor's text input
+fn focus_editor(tree: &mut Tree) {
+    type EditorState = text_input::State<<Renderer as text::Renderer>::Paragraph>;
+
+    if let widget::tree::State::Some(state) = &mut tree.state
+        && let Some(state) = state.downcast_mut::<EditorState>()
+    {
+        state.focus();
+    }
+}
+
+impl<'a, Message: Clone + 'a> From<TreeView<'a, Message>> for Element<'a, Message> {
+    fn from(mut tree_view: TreeView<'a, Message>) -> Self {
+        tree_view.build_editor();
+        Element::new(tree_view)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::tests::sample;
+
+    const BOUNDS: Rectangle = Rectangle {
+        x: 0.0,
+        y: 0.0,
+        width: 200.0,
+        height: 72.0,
+    };
+
+    #[test]
+    fn test_rows_follow_open_state() {
+        let roots = sample();
+        let view = TreeView::<()>::new(&roots);
+
+        let labels: Vec<_> = view.rows.iter().map(|row| row.label).collect();
+        assert_eq!(labels, ["Root", "Branch", "Other", "Second"]);
+    }
+
+    #[test]
+    fn test_filtered_rows_highlight_matches() {
+        let roots = sample();
+        let view = TreeView::<()>::filtered(&roots, &Filter::new("EA"), |_| Decoration::default());
+
+        let rows: Vec<_> = view
+            .rows
+            .iter()
+            .map(|row| (row.label, row.open, row.highlight.clone()))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("Root", true, None),
+                ("Branch", true, None),
+                ("Leaf", false, Some(1..3))
+            ]
+        );
+        assert!(!roots[0].children[0].open);
+    }
+
+    #[test]
+    fn test_placeholder_rows_for_pending_loads() {
+        let mut roots = sample();
+        roots[0].children[0].open = true;
+        roots[0].children[0].children.clear();
+        roots[0].children[0].load = Load::Failed("permission denied".into());
+        roots[0].children[1].open = true;
+        roots[0].children[1].load = Load::Loading;
+        let view = TreeView::<()>::new(&roots);
+
+        let labels: Vec<_> = view.rows.iter().map(|row| row.label).collect();
+        assert_eq!(
+            labels,
+            [
+                "Root",
+                "Branch",
+                "permission denied",
+                "Other",
+                "Loading…",
+                "Second"
+            ]
+        );
+        assert_eq!(view.rows[2].kind, RowKind::Failed);
+        assert_eq!(view.rows[2].depth, 2);
+        assert_eq!(view.rows[2].id, roots[0].children[0].id());
+
+        let bounds = Rectangle {
+            height: 120.0,
+            ..BOUNDS
+        };
+        let failed = Point::new(90.0, 60.0);
+        assert_eq!(view.hit(bounds, failed), None);
+        assert_eq!(
+            TreeView::new(&roots).on_retry(|_| ()).hit(bounds, failed),
+            Some((2, Target::Retry))
+        );
+        assert_eq!(
+            TreeView::new(&roots)
+                .on_retry(|_| ())
+                .hit(bounds, Point::new(90.0, 108.0)),
+            None
+        );
+        assert_eq!(
+            view.drop_target(bounds, Point::new(90.0, 60.0), roots[0].id()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_row_indices_count_placeholders() {
+        let mut roots = sample();
+        roots[0].children[0].open = true;
+        roots[0].children[0].children.clear();
+        roots[0].children[0].load = Load::Loading;
+        let rows = crate::tree::visible_rows(&roots);
+
+        let indices: Vec<_> = row_indices(&rows).collect();
+        let view = TreeView::<()>::new(&roots);
+
+        assert_eq!(indices, [0, 1, 3, 4]);
+        assert_eq!(view.rows[3].label, "Other");
+    }
+
+    #[test]
+    fn test_hit_distinguishes_arrow_and_label() {
+        let roots = sample();
+        let view = TreeView::<()>::new(&roots);
+
+        // Second row is indented one level; its arrow starts at x = 24
+        assert_eq!(
+            view.hit(BOUNDS, Point::new(30.0, 30.0)),
+            Some((1, Target::Arrow))
+        );
+        assert_eq!(
+            view.hit(BOUNDS, Point::new(60.0, 30.0)),
+            Some((1, Target::Label))
+        );
+        assert_eq!(view.hit(BOUNDS, Point::new(10.0, 30.0)), None);
+    }
+
+    #[test]
+    fn test_icon_shifts_label_but_is_part_of_it() {
+        let roots = sample();
+        let view = TreeView::<()>::decorated(&roots, |node| Decoration {
+            icon: (node.label == "Branch").then_some("📁"),
+            ..Decoration::default()
+        });
+
+        let row_bounds = view.row_bounds(BOUNDS, 1);
+        assert_eq!(view.label_x(row_bounds, &view.rows[1]), 72.0);
+        assert_eq!(
+            view.label_x(view.row_bounds(BOUNDS, 2), &view.rows[2]),
+            48.0
+        );
+        assert_eq!(
+            view.hit(BOUNDS, Point::new(55.0, 30.0)),
+            Some((1, Target::Label))
+        );
+    }
+
+    #[test]
+    fn test_check_shifts_label_and_hits_with_handler() {
+        let roots = sample();
+        let decorate = |node: &Node| Decoration {
+            icon: (node.label == "Branch").then_some("📁"),
+            check: Some(CheckState::Unchecked),
+            ..Decoration::default()
+        };
+        let point = Point::new(60.0, 30.0);
+
+        let view = TreeView::<()>::decorated(&roots, decorate);
+        assert_eq!(
+            view.label_x(view.row_bounds(BOUNDS, 1), &view.rows[1]),
+            96.0
+        );
+        assert_eq!(view.hit(BOUNDS, point), None);
+        assert_eq!(
+            TreeView::decorated(&roots, decorate)
+                .on_check(|_| ())
+                .hit(BOUNDS, point),
+            Some((1, Target::Check))
+        );
+    }
+
+    #[test]
+    fn test_hit_leaf_has_no_arrow() {
+        let roots = sample();
+        let view = TreeView::<()>::new(&roots);
+
+        assert_eq!(view.hit(BOUNDS, Point::new(30.0, 60.0)), None);
+        assert_eq!(
+            view.hit(BOUNDS, Point::new(60.0, 60.0)),
+            Some((2, Target::Label))
+        );
+    }
+
+    #[test]
+    fn test_hit_rename_only_with_handler() {
+        let roots = sample();
+        let point = Point::new(190.0, 5.0);
+
+        assert_eq!(
+            TreeView::<()>::new(&roots).hit(BOUNDS, point),
+            Some((0, Target::Label))
+        );
+        assert_eq!(
+            TreeView::new(&roots).on_rename(|_| ()).hit(BOUNDS, point),
+            Some((0, Target::Rename))
+        );
+    }
+
+    #[test]
+    fn test_hit_row_buttons_from_right_edge() {
+        let roots = sample();
+        let view = TreeView::new(&roots)
+            .on_rename(|_| ())
+            .on_add(|_| ())
+            .on_remove(|_| ());
+
+        assert_eq!(
+            view.hit(BOUNDS, Point::new(190.0, 5.0)),
+            Some((0, Target::Rename))
+        );
+        assert_eq!(
+            view.hit(BOUNDS, Point::new(166.0, 5.0)),
+            Some((0, Target::Add))
+        );
+        assert_eq!(
+            view.hit(BOUNDS, Point::new(142.0, 5.0)),
+            Some((0, Target::Remove))
+        );
+
+        // Without a rename handler the add button moves to the edge
+        let view = TreeView::new(&roots).on_add(|_| ());
+        assert_eq!(
+            view.hit(BOUNDS, Point::new(190.0, 5.0)),
+            Some((0, Target::Add))
+        );
+    }
+
+    #[test]
+    fn test_hit_outside_rows() {
+        let roots = sample();
+        let view = TreeView::<()>::new(&roots).spacing(4.0);
+
+        // Gap between the first and second row
+        assert_eq!(view.hit(BOUNDS, Point::new(60.0, 26.0)), None);
+        assert_eq!(view.hit(BOUNDS, Point::new(60.0, 300.0)), None);
+    }
+
+    #[test]
+    fn test_drop_target_positions() {
+        let roots = sample();
+        let view = TreeView::<()>::new(&roots);
+        let other = roots[0].children[1].id();
+
+        assert_eq!(
+            view.drop_target(BOUNDS, Point::new(60.0, 26.0), other),
+            Some((1, DropPosition::Before))
+        );
+        assert_eq!(
+            view.drop_target(BOUNDS, Point::new(60.0, 36.0), other),
+            Some((1, DropPosition::Inside))
+        );
+        assert_eq!(
+            view.drop_target(BOUNDS, Point::new(60.0, 46.0), other),
+            Some((1, DropPosition::After))
+        );
+    }
+
+    #[test]
+    fn test_drop_target_skips_dragged_subtree() {
+        let mut open = sample();
+        open[0].children[0].open = true;
+        let view = TreeView::<()>::new(&open);
+        let root = open[0].id();
+        let branch = open[0].children[0].id();
+
+        let bounds = Rectangle {
+            height: 96.0,
+            ..BOUNDS
+        };
+
+        // Rows: Root, Branch, Leaf, Other, Second
+        assert_eq!(view.drop_target(bounds, Point::new(60.0, 36.0), root), None);
+        assert_eq!(
+            view.drop_target(bounds, Point::new(60.0, 60.0), branch),
+            None
+        );
+        assert_eq!(
+            view.drop_target(bounds, Point::new(60.0, 84.0), branch),
+            Some((3, DropPosition::Inside))
+        );
+    }
+
+    #[test]
+    fn test_visible_range_limited_to_viewport() {
+        let roots: Vec<Node> = (0..100_000)
+            .map(|i| Node::new(&format!("Node {i}"), false, vec![]))
+            .collect();
+        let view = TreeView::<()>::new(&roots);
+        let bounds = Rectangle::new(Point::ORIGIN, Size::new(200.0, 2_400_000.0));
+
+        let viewport = Rectangle::new(Point::new(0.0, 2400.0), Size::new(200.0, 240.0));
+        assert_eq!(view.visible_range(bounds, &viewport), 100..110);
+
+        let partial = Rectangle::new(Point::new(0.0, 12.0), Size::new(200.0, 24.0));
+        assert_eq!(view.visible_range(bounds, &partial), 0..2);
+
+        let past_end = Rectangle::new(Point::new(0.0, 3_000_000.0), Size::new(200.0, 240.0));
+        assert!(view.visible_range(bounds, &past_end).is_empty());
+    }
+}
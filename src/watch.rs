@@ -0,0 +1,633 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self as std_mpsc, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use iced::Subscription;
+use iced::futures::channel::mpsc;
+use iced::futures::{self, SinkExt, Stream, StreamExt};
+use iced::stream;
+use notify::event::{ModifyKind, RenameMode};
+use notify::{EventKind, RecursiveMode, Watcher};
+
+use crate::filesystem::{self, Kind, Payload};
+use crate::tree::{self, Node};
+
+/// How long the first half of a rename waits for its second half before it
+/// is taken as a removal
+const RENAME_WINDOW: Duration = Duration::from_millis(100);
+/// Changes buffered while the application is busy
+const CAPACITY: usize = 100;
+
+/// A change on disk inside a watched folder. Entries that appear carry
+/// their kind, looked up when the change is reported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Created(PathBuf, Kind),
+    Removed(PathBuf),
+    /// Also reported for moves between watched folders
+    Renamed {
+        from: PathBuf,
+        to: PathBuf,
+        kind: Kind,
+    },
+}
+
+/// What the watch subscription reports
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// The watch started; pass the handle to [`Watches::connect`]
+    Started(Handle),
+    Changed(Change),
+}
+
+/// Tells a running watch which folders to add or drop
+#[derive(Debug, Clone)]
+pub struct Handle(mpsc::UnboundedSender<Command>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Command {
+    Watch(PathBuf),
+    Unwatch(PathBuf),
+}
+
+/// The folders of a tree that are being watched.
+///
+/// The set is kept between updates, so the tree is only walked when
+/// [`Watches::update`] is called after folders may have been read or
+/// removed, and only the folders that came or went are passed on to the
+/// watch, which keeps running in the meantime.
+#[derive(Debug, Default)]
+pub struct Watches {
+    folders: HashSet<PathBuf>,
+    handle: Option<Handle>,
+}
+
+impl Watches {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes the handle of a watch that started, and watches the current
+    /// folders through it
+    pub fn connect(&mut self, handle: Handle) {
+        for folder in &self.folders {
+            handle.send(Command::Watch(folder.clone()));
+        }
+        self.handle = Some(handle);
+    }
+
+    /// Watches the read folders of the tree that are not watched yet, and
+    /// stops watching the ones no longer in it
+    pub fn update(&mut self, roots: &[Node<Payload>]) {
+        let folders: HashSet<PathBuf> = folders(roots).into_iter().collect();
+        if let Some(handle) = &self.handle {
+            for gone in self.folders.difference(&folders) {
+                handle.send(Command::Unwatch(gone.clone()));
+            }
+            for new in folders.difference(&self.folders) {
+                handle.send(Command::Watch(new.clone()));
+            }
+        }
+        self.folders = folders;
+    }
+
+    /// Whether changes inside the folder are reported
+    pub fn contains(&self, folder: &Path) -> bool {
+        self.folders.contains(folder)
+    }
+}
+
+impl Handle {
+    /// Drops the command if the watch has stopped
+    fn send(&self, command: Command) {
+        let _ = self.0.unbounded_send(command);
+    }
+}
+
+/// Folders whose entries have been read, i.e. the ones whose changes show
+/// in the tree
+pub fn folders(roots: &[Node<Payload>]) -> Vec<PathBuf> {
+    fn collect(nodes: &[Node<Payload>], folders: &mut Vec<PathBuf>) {
+        for node in nodes {
+            if let Some(entry) = node.data.as_ref().filter(|_| is_listed(node)) {
+                folders.push(entry.path.clone());
+            }
+            collect(&node.children, folders);
+        }
+    }
+
+    let mut folders = Vec::new();
+    collect(roots, &mut folders);
+    folders
+}
+
+/// Reports changes inside the folders given to [`Watches`], but not in
+/// their subfolders. The watch starts with no folders and reports
+/// [`Event::Started`] first.
+pub fn changes() -> Subscription<Event> {
+    Subscription::run(watch)
+}
+
+/// Applies a change to the nodes read from disk. Nodes keep their ids, open
+/// state and children when they are renamed or moved, so selection and
+/// expansion survive. Changes in folders that have not been read are
+/// ignored. Returns whether the tree changed.
+pub fn apply(roots: &mut Vec<Node<Payload>>, change: &Change) -> bool {
+    match change {
+        Change::Created(path, kind) => {
+            if locate(roots, path).is_some() {
+                return false;
+            }
+            let Some(parent) = path
+                .parent()
+                .and_then(|parent| listed_folder(roots, parent))
+            else {
+                return false;
+            };
+
+            insert_sorted(&mut parent.children, filesystem::node(path.clone(), *kind));
+            true
+        }
+        Change::Removed(path) => match locate(roots, path) {
+            Some(at) => {
+                take(roots, &at);
+                true
+            }
+            None => false,
+        },
+        Change::Renamed { from, to, kind } => {
+            // A rename replaces whatever was at the destination
+            if from != to
+                && let Some(existing) = locate(roots, to)
+            {
+                take(roots, &existing);
+            }
+            let Some(at) = locate(roots, from) else {
+                return apply(roots, &Change::Created(to.clone(), *kind));
+            };
+
+            let mut node = take(roots, &at);
+            rebase(&mut node, from, to);
+            node.label = filesystem::name(to);
+
+            if from.parent() == to.parent() {
+                let siblings =
+                    siblings_mut(roots, &at[..at.len() - 1]).expect("parent is unchanged");
+                insert_sorted(siblings, node);
+            } else if let Some(parent) = to.parent().and_then(|parent| listed_folder(roots, parent))
+            {
+                insert_sorted(&mut parent.children, node);
+            }
+            true
+        }
+    }
+}
+
+/// Whether a node is a folder whose entries have been read
+fn is_listed(node: &Node<Payload>) -> bool {
+    node.load.is_loaded() && node.data.as_ref().is_some_and(|entry| entry.is_folder())
+}
+
+/// The node read from the folder at `path`, if its entries are in the tree
+fn listed_folder<'a>(roots: &'a mut [Node<Payload>], path: &Path) -> Option<&'a mut Node<Payload>> {
+    let at = locate(roots, path)?;
+    tree::get_mut(roots, &at).filter(|node| is_listed(node))
+}
+
+/// Index path of the node read from `path`
+fn locate(nodes: &[Node<Payload>], path: &Path) -> Option<Vec<usize>> {
+    nodes.iter().enumerate().find_map(|(index, node)| {
+        if node.data.as_ref().is_some_and(|entry| entry.path == path) {
+            return Some(vec![index]);
+        }
+        let mut at = locate(&node.children, path)?;
+        at.insert(0, index);
+        Some(at)
+    })
+}
+
+/// The roots, or the children of the node at `parent`
+fn siblings_mut<'a>(
+    roots: &'a mut Vec<Node<Payload>>,
+    parent: &[usize],
+) -> Option<&'a mut Vec<Node<Payload>>> {
+    if parent.is_empty() {
+        Some(roots)
+    } else {
+        tree::get_mut(roots, parent).map(|node| &mut node.children)
+    }
+}
+
+/// Detaches the node at a located index path
+fn take(roots: &mut Vec<Node<Payload>>, at: &[usize]) -> Node<Payload> {
+    let (&index, parent) = at.split_last().expect("index paths are not empty");
+    siblings_mut(roots, parent)
+        .expect("located nodes exist")
+        .remove(index)
+}
+
+/// Inserts before the first sibling read from disk that sorts after `node`.
+/// Siblings without an entry are not ordered and are skipped.
+fn insert_sorted(siblings: &mut Vec<Node<Payload>>, node: Node<Payload>) {
+    let key = |node: &Node<Payload>| {
+        node.data
+            .as_ref()
+            .map(|entry| filesystem::sort_key(entry.kind, &entry.path))
+    };
+    let new = key(&node);
+    let index = siblings
+        .iter()
+        .position(|sibling| key(sibling).is_some_and(|sibling| Some(sibling) > new))
+        .unwrap_or(siblings.len());
+    siblings.insert(index, node);
+}
+
+/// Moves the paths of a subtree from under `from` to under `to`
+fn rebase(node: &mut Node<Payload>, from: &Path, to: &Path) {
+    if let Some(entry) = &mut node.data
+        && let Ok(rest) = entry.path.strip_prefix(from)
+    {
+        entry.path = if rest.as_os_str().is_empty() {
+            to.to_owned()
+        } else {
+            to.join(rest)
+        };
+    }
+    for child in &mut node.children {
+        rebase(child, from, to);
+    }
+}
+
+/// What the watch waits for
+enum Input {
+    Command(Command),
+    Changed(Change),
+}
+
+/// Streams the changes reported by the platform's watcher (inotify on
+/// Linux) for the folders it is told to watch
+fn watch() -> impl Stream<Item = Event> {
+    stream::channel(CAPACITY, async move |mut output| {
+        let (events, received) = std_mpsc::channel();
+        let Ok(mut watcher) = notify::recommended_watcher(events) else {
+            return;
+        };
+        let (sender, changes) = mpsc::unbounded();
+        thread::spawn(move || pair(&received, &sender));
+
+        let (handle, commands) = mpsc::unbounded();
+        if output.send(Event::Started(Handle(handle))).await.is_err() {
+            return;
+        }
+        let mut inputs =
+            futures::stream::select(commands.map(Input::Command), changes.map(Input::Changed));
+        while let Some(input) = inputs.next().await {
+            match input {
+                Input::Command(Command::Watch(folder)) => {
+                    // A folder may be gone by the time it is watched
+                    let _ = watcher.watch(&folder, RecursiveMode::NonRecursive);
+                }
+                Input::Command(Command::Unwatch(folder)) => {
+                    let _ = watcher.unwatch(&folder);
+                }
+                Input::Changed(change) => {
+                    if output.send(Event::Changed(change)).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Turns the watcher's events into changes on a thread of its own, where
+/// waiting for the second half of a rename needs no async timer. Returns
+/// once the watcher is dropped.
+fn pair(
+    events: &std_mpsc::Receiver<notify::Result<notify::Event>>,
+    changes: &mpsc::UnboundedSender<Change>,
+) {
+    let mut pairing = Pairing::default();
+    loop {
+        let event = if pairing.is_waiting() {
+            match events.recv_timeout(RENAME_WINDOW) {
+                Ok(event) => event,
+                Err(RecvTimeoutError::Timeout) => {
+                    if let Some(change) = pairing.flush() {
+                        let _ = changes.unbounded_send(change);
+                    }
+                    continue;
+                }
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        } else {
+            match events.recv() {
+                Ok(event) => event,
+                Err(_) => return,
+            }
+        };
+
+        if let Ok(event) = event {
+            for change in pairing.push(event) {
+                if changes.unbounded_send(change).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Turns watcher events into changes, joining the two halves of a rename
+/// so the renamed node can keep its identity
+#[derive(Debug, Default)]
+struct Pairing {
+    /// A path moved away, with the tracker of the rename it belongs to
+    moved_out: Option<(usize, PathBuf)>,
+}
+
+impl Pairing {
+    fn is_waiting(&self) -> bool {
+        self.moved_out.is_some()
+    }
+
+    /// Gives up waiting for the second half of a rename
+    fn flush(&mut self) -> Option<Change> {
+        self.moved_out.take().map(|(_, path)| Change::Removed(path))
+    }
+
+    fn push(&mut self, event: notify::Event) -> Vec<Change> {
+        let tracker = event.tracker();
+        let mut paths = event.paths.into_iter();
+        let mut changes = Vec::new();
+
+        match event.kind {
+            EventKind::Modify(ModifyKind::Name(RenameMode::To))
+                if tracker.is_some()
+                    && self.moved_out.as_ref().map(|(pending, _)| *pending) == tracker =>
+            {
+                // The event naming both paths follows
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+                self.moved_out = None;
+                if let (Some(from), Some(to)) = (paths.next(), paths.next()) {
+                    let kind = kind_of(&to);
+                    changes.push(Change::Renamed { from, to, kind });
+                }
+            }
+            kind => {
+                changes.extend(self.flush());
+                match kind {
+                    EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                        // Untracked, it is a watched folder itself moving,
+                        // which its parent folder reports
+                        if let (Some(tracker), Some(path)) = (tracker, paths.next()) {
+                            self.moved_out = Some((tracker, path));
+                        }
+                    }
+                    EventKind::Modify(ModifyKind::Name(RenameMode::To)) | EventKind::Create(_) => {
+                        changes.extend(paths.map(created));
+                    }
+                    EventKind::Remove(_) => changes.extend(paths.map(Change::Removed)),
+                    EventKind::Modify(ModifyKind::Name(_)) => {
+                        // Platforms that do not tell which half of a rename
+                        // a path is
+                        changes.extend(paths.map(|path| {
+                            if path.exists() {
+                                created(path)
+                            } else {
+                                Change::Removed(path)
+                            }
+                        }));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        changes
+    }
+}
+
+fn created(path: PathBuf) -> Change {
+    let kind = kind_of(&path);
+    Change::Created(path, kind)
+}
+
+/// Whether an entry is a file or a folder, following symbolic links like
+/// [`filesystem::read_dir`]. An entry that is already gone counts as a file.
+fn kind_of(path: &Path) -> Kind {
+    if path.is_dir() {
+        Kind::Folder
+    } else {
+        Kind::File
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use notify::event::{CreateKind, RemoveKind};
+
+    use super::*;
+    use crate::filesystem::tests::touch;
+    use crate::tree::Load;
+    use crate::tree::tests::labels;
+
+    #[test]
+    fn test_created_inserts_at_sorted_position() {
+        let temp = tempfile::tempdir().unwrap();
+        touch(temp.path(), "b.txt");
+        touch(temp.path(), "src/bin/main.rs");
+        let mut roots = vec![filesystem::open(temp.path()).unwrap()];
+
+        let created = touch(temp.path(), "a.txt");
+        // The kind comes with the change; the disk is not consulted
+        let docs = temp.path().join("docs");
+
+        assert!(apply(
+            &mut roots,
+            &Change::Created(created.clone(), Kind::File)
+        ));
+        assert!(apply(&mut roots, &Change::Created(docs, Kind::Folder)));
+        assert!(!apply(&mut roots, &Change::Created(created, Kind::File)));
+        assert_eq!(
+            labels(&roots[0].children),
+            ["docs", "src", "a.txt", "b.txt"]
+        );
+
+        // Folders that have not been read are left alone
+        let unread = touch(temp.path(), "src/bin/tool.rs");
+        assert!(!apply(&mut roots, &Change::Created(unread, Kind::File)));
+    }
+
+    #[test]
+    fn test_removed_drops_node() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = touch(temp.path(), "gone.txt");
+        touch(temp.path(), "kept.txt");
+        let mut roots = vec![filesystem::open(temp.path()).unwrap()];
+
+        assert!(apply(&mut roots, &Change::Removed(path.clone())));
+        assert!(!apply(&mut roots, &Change::Removed(path)));
+        assert_eq!(labels(&roots[0].children), ["kept.txt"]);
+    }
+
+    #[test]
+    fn test_renamed_keeps_identity_and_subtree() {
+        let temp = tempfile::tempdir().unwrap();
+        touch(temp.path(), "old/inner/deep.txt");
+        touch(temp.path(), "other/x.txt");
+        let mut roots = vec![filesystem::open(temp.path()).unwrap()];
+        let old = &mut roots[0].children[0];
+        old.children = filesystem::read_dir(&temp.path().join("old")).unwrap();
+        old.load = Load::Loaded;
+        old.open = true;
+        let id = old.id();
+
+        let from = temp.path().join("old");
+        let to = temp.path().join("zzz");
+        assert!(apply(
+            &mut roots,
+            &Change::Renamed {
+                from,
+                to: to.clone(),
+                kind: Kind::Folder
+            }
+        ));
+
+        let renamed = &roots[0].children[1];
+        assert_eq!(labels(&roots[0].children), ["other", "zzz"]);
+        assert_eq!(renamed.id(), id);
+        assert!(renamed.open);
+        let inner = renamed.children[0].data.as_ref().unwrap();
+        assert_eq!(inner.path, to.join("inner"));
+
+        // Moving into another listed folder carries the node along
+        let other = &mut roots[0].children[0];
+        other.children = filesystem::read_dir(&temp.path().join("other")).unwrap();
+        other.load = Load::Loaded;
+        let to_other = temp.path().join("other/zzz");
+        assert!(apply(
+            &mut roots,
+            &Change::Renamed {
+                from: to,
+                to: to_other,
+                kind: Kind::Folder
+            }
+        ));
+        assert_eq!(labels(&roots[0].children), ["other"]);
+        assert_eq!(labels(&roots[0].children[0].children), ["zzz", "x.txt"]);
+        assert_eq!(roots[0].children[0].children[0].id(), id);
+    }
+
+    #[test]
+    fn test_folders_lists_read_folders() {
+        let temp = tempfile::tempdir().unwrap();
+        touch(temp.path(), "a/b/c.txt");
+        let mut roots = vec![filesystem::open(temp.path()).unwrap()];
+        assert_eq!(folders(&roots), vec![temp.path().to_path_buf()]);
+
+        roots[0].children[0].load = Load::Loaded;
+        assert_eq!(
+            folders(&roots),
+            [temp.path().to_path_buf(), temp.path().join("a")]
+        );
+    }
+
+    #[test]
+    fn test_watches_send_only_folders_that_changed() {
+        let temp = tempfile::tempdir().unwrap();
+        touch(temp.path(), "a/b/c.txt");
+        let mut roots = vec![filesystem::open(temp.path()).unwrap()];
+        let mut watches = Watches::new();
+        watches.update(&roots);
+
+        // Folders read before the watch started are sent when it does
+        let (sender, mut commands) = mpsc::unbounded();
+        watches.connect(Handle(sender));
+        let root = temp.path().to_path_buf();
+        assert_eq!(commands.try_next().unwrap(), Some(Command::Watch(root)));
+
+        watches.update(&roots);
+        assert!(commands.try_next().is_err());
+
+        roots[0].children[0].load = Load::Loaded;
+        watches.update(&roots);
+        let folder = temp.path().join("a");
+        assert_eq!(
+            commands.try_next().unwrap(),
+            Some(Command::Watch(folder.clone()))
+        );
+        assert!(watches.contains(&folder));
+
+        roots[0].children.clear();
+        watches.update(&roots);
+        assert_eq!(
+            commands.try_next().unwrap(),
+            Some(Command::Unwatch(folder.clone()))
+        );
+        assert!(!watches.contains(&folder));
+        assert!(commands.try_next().is_err());
+    }
+
+    #[test]
+    fn test_pairing_joins_rename_halves() {
+        let mut pairing = Pairing::default();
+        let rename =
+            |mode| notify::Event::new(EventKind::Modify(ModifyKind::Name(mode))).set_tracker(7);
+
+        assert!(
+            pairing
+                .push(rename(RenameMode::From).add_path("/a".into()))
+                .is_empty()
+        );
+        assert!(
+            pairing
+                .push(rename(RenameMode::To).add_path("/b".into()))
+                .is_empty()
+        );
+        assert_eq!(
+            pairing.push(
+                rename(RenameMode::Both)
+                    .add_path("/a".into())
+                    .add_path("/b".into())
+            ),
+            [Change::Renamed {
+                from: "/a".into(),
+                to: "/b".into(),
+                kind: Kind::File
+            }]
+        );
+        assert!(!pairing.is_waiting());
+    }
+
+    #[test]
+    fn test_pairing_unmatched_move_is_removal() {
+        let mut pairing = Pairing::default();
+        let from = notify::Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::From)))
+            .set_tracker(1)
+            .add_path("/moved".into());
+
+        assert!(pairing.push(from).is_empty());
+        assert_eq!(
+            pairing.push(
+                notify::Event::new(EventKind::Create(CreateKind::File)).add_path("/new".into())
+            ),
+            [
+                Change::Removed("/moved".into()),
+                Change::Created("/new".into(), Kind::File)
+            ]
+        );
+
+        let from = notify::Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::From)))
+            .set_tracker(2)
+            .add_path("/away".into());
+        pairing.push(from);
+        assert_eq!(pairing.flush(), Some(Change::Removed("/away".into())));
+        assert_eq!(
+            pairing
+                .push(notify::Event::new(EventKind::Remove(RemoveKind::Any)).add_path("/x".into())),
+            [Change::Removed("/x".into())]
+        );
+    }
+}